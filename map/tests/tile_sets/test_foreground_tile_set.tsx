@@ -0,0 +1,4 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="test_foreground_tile_set" tilewidth="8" tileheight="8" tilecount="2" columns="2">
+ <image source="test_foreground_tile_set.png" width="16" height="8"/>
+</tileset>
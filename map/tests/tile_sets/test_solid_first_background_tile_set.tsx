@@ -0,0 +1,6 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="test_solid_first_background_tile_set" tilewidth="8" tileheight="8" tilecount="2" columns="2">
+ <image source="test_solid_first_background_tile_set.png" width="16" height="8"/>
+ <tile id="0" type="Collision"/>
+ <tile id="1" type="Win"/>
+</tileset>
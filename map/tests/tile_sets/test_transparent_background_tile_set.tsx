@@ -0,0 +1,15 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="test_transparent_background_tile_set" tilewidth="8" tileheight="8" tilecount="5" columns="5">
+ <properties>
+  <property name="Transparent Colour" type="color" value="#ffff00ff"/>
+ </properties>
+ <image source="test_background_tile_set.png" width="40" height="8"/>
+ <tile id="1" type="Collision"/>
+ <tile id="2" type="Win"/>
+ <tile id="3" type="Kill"/>
+ <tile id="4" type="Collision">
+  <objectgroup draworder="index" id="2">
+   <object id="1" x="0" y="4" width="8" height="4"/>
+  </objectgroup>
+ </tile>
+</tileset>
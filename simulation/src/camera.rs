@@ -0,0 +1,182 @@
+use crate::input::Tri;
+use crate::types::{FixedNumberType, SCREEN_HEIGHT, SCREEN_WIDTH, TILE_SIZE};
+use crate::world::World;
+use agb_fixnum::Vector2D;
+
+/// Half the size of the box around the focus the player can move in before the camera follows
+const DEAD_ZONE: Vector2D<i32> = Vector2D::new(16, 24);
+/// How far ahead of the player the camera looks in the direction they face
+const LOOK_AHEAD: i32 = 32;
+/// The camera covers 1 / SMOOTHING of the distance to where it wants to be each frame
+const SMOOTHING: i32 = 4;
+/// How far above the centre of the screen the player can get while the height is locked
+const MAX_ABOVE_LOCK: i32 = SCREEN_HEIGHT / 2 - 16;
+
+pub struct Camera {
+    /// Top left of the screen in the level
+    pub position: Vector2D<FixedNumberType>,
+    pub dead_zone: Vector2D<i32>,
+    pub look_ahead: i32,
+    pub smoothing: i32,
+    /// The point the camera tries to keep in the centre of the screen
+    focus: Vector2D<FixedNumberType>,
+    look_ahead_offset: FixedNumberType,
+    /// Height the camera holds at while the player is off the ground
+    locked_y: Option<FixedNumberType>,
+    shake_frames: i32,
+    shake_length: i32,
+    shake_magnitude: i32,
+}
+
+impl Camera {
+    pub fn new(level: &World, player_position: Vector2D<FixedNumberType>) -> Self {
+        let mut camera = Camera {
+            position: (0, 0).into(),
+            dead_zone: DEAD_ZONE,
+            look_ahead: LOOK_AHEAD,
+            smoothing: SMOOTHING,
+            focus: player_position,
+            look_ahead_offset: 0.into(),
+            locked_y: None,
+            shake_frames: 0,
+            shake_length: 0,
+            shake_magnitude: 0,
+        };
+        camera.position = camera.target_position(level, player_position);
+        camera
+    }
+
+    /// Moves the camera towards the player and returns the new position
+    pub fn update(
+        &mut self,
+        level: &World,
+        player_position: Vector2D<FixedNumberType>,
+        facing: Tri,
+        is_on_ground: bool,
+    ) -> Vector2D<FixedNumberType> {
+        let look_ahead_target: FixedNumberType = (facing as i32 * self.look_ahead).into();
+        self.look_ahead_offset += (look_ahead_target - self.look_ahead_offset) / self.smoothing;
+
+        // Hold the height the jump started from until landing, unless the player falls below
+        // it or gets close to going off the top of the screen
+        let target_y = if is_on_ground {
+            self.locked_y = None;
+            player_position.y
+        } else {
+            let locked_y = *self.locked_y.get_or_insert(self.focus.y);
+            if player_position.y > locked_y {
+                player_position.y
+            } else {
+                locked_y.min(player_position.y + MAX_ABOVE_LOCK)
+            }
+        };
+
+        let target: Vector2D<FixedNumberType> =
+            (player_position.x + self.look_ahead_offset, target_y).into();
+        self.focus.x = follow_dead_zone(self.focus.x, target.x, self.dead_zone.x);
+        self.focus.y = follow_dead_zone(self.focus.y, target.y, self.dead_zone.y);
+
+        let target_position = self.target_position(level, player_position);
+        self.position += (target_position - self.position) / self.smoothing;
+        self.position
+    }
+
+    /// Shakes the screen for `frames` frames, starting `magnitude` pixels out and settling down.
+    /// A weaker shake won't cut short a stronger one already going
+    pub fn shake(&mut self, frames: i32, magnitude: i32) {
+        if magnitude * frames >= self.shake_magnitude * self.shake_frames {
+            self.shake_frames = frames;
+            self.shake_length = frames;
+            self.shake_magnitude = magnitude;
+        }
+    }
+
+    /// The screen shake offset for this frame, moves the shake on a frame each call
+    pub fn shake_offset(&mut self) -> Vector2D<i32> {
+        if self.shake_frames <= 0 {
+            return (0, 0).into();
+        }
+
+        let magnitude = self.shake_magnitude * self.shake_frames / self.shake_length;
+        let x = if self.shake_frames % 2 == 0 {
+            magnitude
+        } else {
+            -magnitude
+        };
+        let y = if (self.shake_frames / 2) % 2 == 0 {
+            magnitude
+        } else {
+            -magnitude
+        };
+
+        self.shake_frames -= 1;
+        (x, y).into()
+    }
+
+    /// Where the camera would be with the focus in the middle of the screen, kept inside the
+    /// camera bounds the player is in or the level if there are none. Overlapping bounds act as
+    /// one region, so the camera can be anywhere any of them would allow
+    fn target_position(
+        &self,
+        level: &World,
+        player_position: Vector2D<FixedNumberType>,
+    ) -> Vector2D<FixedNumberType> {
+        let player = player_position.floor();
+        let half_screen: Vector2D<i32> = (SCREEN_WIDTH / 2, SCREEN_HEIGHT / 2).into();
+        let desired = self.focus.floor() - half_screen;
+
+        let level_bounds = (
+            0,
+            0,
+            (level.dimensions.x * TILE_SIZE) as i32,
+            (level.dimensions.y * TILE_SIZE) as i32,
+        );
+
+        level
+            .camera_bounds
+            .iter()
+            .copied()
+            .filter(|&(x, y, width, height)| {
+                player.x >= x && player.x < x + width && player.y >= y && player.y < y + height
+            })
+            .map(|bounds| clamp_to_bounds(desired, bounds))
+            .min_by_key(|position| (position.x - desired.x).abs() + (position.y - desired.y).abs())
+            .unwrap_or_else(|| clamp_to_bounds(desired, level_bounds))
+            .into()
+    }
+}
+
+/// Keeps the screen inside the `(x, y, width, height)` bounds
+fn clamp_to_bounds(position: Vector2D<i32>, bounds: (i32, i32, i32, i32)) -> Vector2D<i32> {
+    let (x, y, width, height) = bounds;
+    (
+        clamp_axis(position.x, x, width, SCREEN_WIDTH),
+        clamp_axis(position.y, y, height, SCREEN_HEIGHT),
+    )
+        .into()
+}
+
+/// Keeps the screen inside `start..start + length`, or centres it there when that is
+/// smaller than the screen
+fn clamp_axis(position: i32, start: i32, length: i32, screen: i32) -> i32 {
+    if length <= screen {
+        start - (screen - length) / 2
+    } else {
+        position.clamp(start, start + length - screen)
+    }
+}
+
+/// Moves `current` just enough that `target` is within `dead_zone` of it
+fn follow_dead_zone(
+    current: FixedNumberType,
+    target: FixedNumberType,
+    dead_zone: i32,
+) -> FixedNumberType {
+    if target > current + dead_zone {
+        target - dead_zone
+    } else if target < current - dead_zone {
+        target + dead_zone
+    } else {
+        current
+    }
+}
@@ -0,0 +1,39 @@
+//! Levels store their layers and collision as a grid of chunks of tiles where each different
+//! chunk is only stored once, so the empty and repeated parts of a level take up very little
+//! ROM while any tile can still be looked up straight away
+
+/// Layers are stored in chunks of `CHUNK_SIZE` by `CHUNK_SIZE` tiles
+pub const CHUNK_SIZE: usize = 4;
+
+/// A grid of tiles split into chunks, each different chunk is only stored once
+pub struct ChunkedLayer {
+    /// In chunks
+    pub width: usize,
+    /// The index in `chunks` of every chunk of the layer, a row at a time
+    pub chunk_map: &'static [u16],
+    /// The tiles of each different chunk, a row at a time
+    pub chunks: &'static [[u16; CHUNK_SIZE * CHUNK_SIZE]],
+}
+
+impl ChunkedLayer {
+    /// The tile at `x`, `y`, which has to be inside the layer
+    pub fn tile(&self, x: usize, y: usize) -> u16 {
+        let chunk = self.chunk_map[self.chunk_index(x, y)];
+        self.chunks[chunk as usize][Self::tile_index(x, y)]
+    }
+
+    /// The tile at `x`, `y`, or `None` if its chunk is missing
+    pub fn get(&self, x: usize, y: usize) -> Option<u16> {
+        let chunk = self.chunk_map.get(self.chunk_index(x, y))?;
+        let chunk = self.chunks.get(*chunk as usize)?;
+        Some(chunk[Self::tile_index(x, y)])
+    }
+
+    fn chunk_index(&self, x: usize, y: usize) -> usize {
+        (y / CHUNK_SIZE) * self.width + x / CHUNK_SIZE
+    }
+
+    fn tile_index(x: usize, y: usize) -> usize {
+        (y % CHUNK_SIZE) * CHUNK_SIZE + x % CHUNK_SIZE
+    }
+}
@@ -1,22 +1,10 @@
-use crate::entities::entity::Entity;
-use crate::level::Level;
-use crate::player::{Player, PlayerAction};
+use crate::entity::{Entity, GROUND_FRICTION};
+use crate::player::PlayerAction;
+use crate::sprite::{Animation, Sprite};
+use crate::tilemap;
 use crate::types::FixedNumberType;
-use agb::display::object::Graphics;
-use agb::{
-    display::object::{OamManaged, Tag},
-    fixnum::Vector2D,
-    println,
-};
-
-pub const BOAR_IDLE: &Graphics = agb::include_aseprite!("gfx/boar/Idle.aseprite");
-pub const BOAR_IDLE_ANIMATION: &Tag = BOAR_IDLE.tags().get("idle");
-
-pub const BOAR_HIT: &Graphics = agb::include_aseprite!("gfx/boar/Hit.aseprite");
-pub const BOAR_HIT_ANIMATION: &Tag = BOAR_HIT.tags().get("hit");
-
-pub const BOAR_RUN: &Graphics = agb::include_aseprite!("gfx/boar/Run.aseprite");
-pub const BOAR_RUN_ANIMATION: &Tag = BOAR_RUN.tags().get("run");
+use crate::world::World;
+use agb_fixnum::Vector2D;
 
 // const SLIME_IDLE: &Tag = TAG_MAP.get("Slime Idle");
 // const SLIME_JUMP: &Tag = TAG_MAP.get("Slime Jump");
@@ -30,12 +18,13 @@ pub const BOAR_RUN_ANIMATION: &Tag = BOAR_RUN.tags().get("run");
 enum UpdateState {
     Nothing,
     KillPlayer,
+    Killed,
     Remove,
 }
 
 #[derive(Default)]
-pub enum Enemy<'a> {
-    Boar(Boar<'a>),
+pub enum Enemy<S> {
+    Boar(Boar<S>),
     #[default]
     Empty,
 }
@@ -43,11 +32,13 @@ pub enum Enemy<'a> {
 pub enum EnemyUpdateState {
     None,
     KillPlayer,
+    /// The enemy was hit by the player this frame
+    Killed,
 }
 
-impl<'a> Enemy<'a> {
-    pub fn new_boar(object: &'a OamManaged, start_pos: Vector2D<FixedNumberType>) -> Self {
-        Enemy::Boar(Boar::new(object, start_pos + (0, 0).into()))
+impl<S: Sprite> Enemy<S> {
+    pub fn new_boar(sprite: S, start_pos: Vector2D<FixedNumberType>) -> Self {
+        Enemy::Boar(Boar::new(sprite, start_pos + (0, 0).into()))
     }
 
     // pub fn collides_with_hat(&self, position: Vector2D<FixedNumberType>) -> bool {
@@ -59,8 +50,7 @@ impl<'a> Enemy<'a> {
 
     pub fn update(
         &mut self,
-        controller: &'a OamManaged,
-        level: &Level,
+        level: &World,
         player_pos: Vector2D<FixedNumberType>,
         player_action: &PlayerAction,
         timer: i32,
@@ -68,7 +58,7 @@ impl<'a> Enemy<'a> {
         let update_state = match self {
             // Enemy::Slime(slime) => slime.update(controller, level, player_pos, timer),
             // Enemy::Snail(snail) => snail.update(controller, level, player_pos, timer),
-            Enemy::Boar(boar) => boar.update(controller, level, player_pos, &player_action, timer),
+            Enemy::Boar(boar) => boar.update(level, player_pos, player_action, timer),
             Enemy::Empty => UpdateState::Nothing,
         };
 
@@ -78,6 +68,7 @@ impl<'a> Enemy<'a> {
                 EnemyUpdateState::None
             }
             UpdateState::KillPlayer => EnemyUpdateState::KillPlayer,
+            UpdateState::Killed => EnemyUpdateState::Killed,
             UpdateState::Nothing => EnemyUpdateState::None,
         }
     }
@@ -90,25 +81,25 @@ impl<'a> Enemy<'a> {
     }
 }
 
-struct EnemyInfo<'a> {
-    entity: Entity<'a>,
+struct EnemyInfo<S> {
+    entity: Entity<S>,
 }
 
-impl<'a> EnemyInfo<'a> {
+impl<S: Sprite> EnemyInfo<S> {
     fn new(
-        object: &'a OamManaged,
+        sprite: S,
         start_pos: Vector2D<FixedNumberType>,
         collision: Vector2D<u16>,
         offset: Option<Vector2D<i32>>,
     ) -> Self {
         let mut enemy_info = EnemyInfo {
-            entity: Entity::new(object, collision, offset),
+            entity: Entity::new(sprite, collision, offset),
         };
         enemy_info.entity.position = start_pos;
         enemy_info
     }
 
-    fn update(&mut self, level: &Level) {
+    fn update(&mut self, level: &World) {
         for &enemy_stop in level.enemy_stops {
             if (self.entity.position + self.entity.velocity - enemy_stop.into())
                 .manhattan_distance()
@@ -117,9 +108,20 @@ impl<'a> EnemyInfo<'a> {
                 self.entity.velocity = (0, 0).into();
             }
         }
+
+        // Enemies can't swim so stay out of the water
+        if self
+            .entity
+            .flags_at_point(level, self.entity.position + self.entity.velocity)
+            & tilemap::WATER_TILE
+            != 0
+        {
+            self.entity.velocity = (0, 0).into();
+        }
         // println!("Enemy Velocity: {:?}", self.entity.velocity);
-        self.entity.position = self.entity.position + self.entity.velocity;
+        self.entity.position += self.entity.velocity;
         self.entity.update_position(level);
+        self.entity.ride_conveyor(level);
     }
 
     fn commit(&mut self, background_offset: Vector2D<FixedNumberType>) {
@@ -133,34 +135,32 @@ enum BoarState {
     Dying(i32),   // the start frame of the dying animation
 }
 
-pub struct Boar<'a> {
-    enemy_info: EnemyInfo<'a>,
+pub struct Boar<S> {
+    enemy_info: EnemyInfo<S>,
     state: BoarState,
 }
 
-impl<'a> Boar<'a> {
-    fn new(object: &'a OamManaged, start_pos: Vector2D<FixedNumberType>) -> Self {
-        let Boar = Boar {
+impl<S: Sprite> Boar<S> {
+    fn new(sprite: S, start_pos: Vector2D<FixedNumberType>) -> Self {
+        Boar {
             enemy_info: EnemyInfo::new(
-                object,
+                sprite,
                 start_pos,
                 (28u16, 14u16).into(),
                 Some((0, -12).into()),
             ),
             state: BoarState::Idle,
-        };
-
-        Boar
+        }
     }
 
     fn update(
         &mut self,
-        controller: &'a OamManaged,
-        level: &Level,
+        level: &World,
         player_pos: Vector2D<FixedNumberType>,
         player_action: &PlayerAction,
         timer: i32,
     ) -> UpdateState {
+        let mut update_state = UpdateState::Nothing;
         let player_has_collided =
             (self.enemy_info.entity.position - player_pos).magnitude_squared() < (15 * 15).into();
 
@@ -168,10 +168,14 @@ impl<'a> Boar<'a> {
             BoarState::Idle => {
                 let offset = (timer / 16) as usize;
 
-                let frame = BOAR_IDLE_ANIMATION.animation_sprite(offset);
-                let sprite = controller.sprite(frame);
+                self.enemy_info.entity.velocity = self.enemy_info.entity.velocity
+                    * self.enemy_info.entity.ground_friction(level)
+                    / 64;
 
-                self.enemy_info.entity.sprite.set_sprite(sprite);
+                self.enemy_info
+                    .entity
+                    .sprite
+                    .set_animation(Animation::BoarIdle, offset);
 
                 if (self.enemy_info.entity.position - player_pos).magnitude_squared()
                     < (64 * 64).into()
@@ -192,6 +196,7 @@ impl<'a> Boar<'a> {
                 if player_has_collided {
                     if *player_action == PlayerAction::Attack {
                         self.state = BoarState::Dying(timer);
+                        update_state = UpdateState::Killed;
                     } else {
                         return UpdateState::KillPlayer;
                     }
@@ -205,17 +210,22 @@ impl<'a> Boar<'a> {
                 }
 
                 if offset >= 7 {
-                    self.enemy_info.entity.velocity = (0, 0).into();
+                    // Keeps sliding on slippery ground
+                    if self.enemy_info.entity.ground_friction(level) == GROUND_FRICTION {
+                        self.enemy_info.entity.velocity = (0, 0).into();
+                    }
                     self.state = BoarState::Idle;
                 } else {
-                    let frame = BOAR_RUN_ANIMATION.animation_sprite(offset);
-                    let sprite = controller.sprite(frame);
-                    self.enemy_info.entity.sprite.set_sprite(sprite);
+                    self.enemy_info
+                        .entity
+                        .sprite
+                        .set_animation(Animation::BoarRun, offset);
                 }
 
                 if player_has_collided {
                     if *player_action == PlayerAction::Attack {
                         self.state = BoarState::Dying(timer);
+                        update_state = UpdateState::Killed;
                     } else {
                         return UpdateState::KillPlayer;
                     }
@@ -233,16 +243,16 @@ impl<'a> Boar<'a> {
                     return UpdateState::Remove;
                 }
 
-                let frame = BOAR_HIT_ANIMATION.animation_sprite(offset);
-                let sprite = controller.sprite(frame);
-
-                self.enemy_info.entity.sprite.set_sprite(sprite);
+                self.enemy_info
+                    .entity
+                    .sprite
+                    .set_animation(Animation::BoarHit, offset);
             }
         }
 
         self.enemy_info.update(level);
 
-        UpdateState::Nothing
+        update_state
     }
 
     fn commit(&mut self, background_offset: Vector2D<FixedNumberType>) {
@@ -0,0 +1,261 @@
+use crate::sprite::Sprite;
+use crate::tilemap;
+use crate::types::{FixedNumberType, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::world::World;
+use agb_fixnum::Vector2D;
+
+/// Velocity kept each frame on the ground, out of 64
+pub const GROUND_FRICTION: i32 = 54;
+pub const ICE_FRICTION: i32 = 62;
+
+/// Pixels per frame a conveyor carries whatever stands on it
+const CONVEYOR_VELOCITY: i32 = 1;
+
+pub struct Entity<S> {
+    pub sprite: S,
+    pub size: Vector2D<i32>,
+    pub position: Vector2D<FixedNumberType>,
+    pub velocity: Vector2D<FixedNumberType>,
+    pub collision_mask: Vector2D<u16>,
+    ///Only applies to the sprite, not the collision mask or position
+    pub sprite_offset: Vector2D<i32>,
+}
+
+impl<S: Sprite> Entity<S> {
+    pub fn new(sprite: S, collision_mask: Vector2D<u16>, offset: Option<Vector2D<i32>>) -> Self {
+        Entity {
+            sprite,
+            collision_mask,
+            size: (16, 16).into(),
+            position: (0, 0).into(),
+            velocity: (0, 0).into(),
+            sprite_offset: offset.unwrap_or(Vector2D::new(0, 0)),
+        }
+    }
+
+    /// Calls `something_fn` with every tile under the collision mask along with a hit mask of
+    /// the pixels the entity covers in that tile, so tiles with partial collision shapes work
+    fn something_at_point<T: FnMut(i32, i32, u64) -> bool>(
+        &self,
+        position: Vector2D<FixedNumberType>,
+        mut something_fn: T,
+    ) -> bool {
+        let left = (position.x - self.collision_mask.x as i32 / 2).floor();
+        let right = (position.x + self.collision_mask.x as i32 / 2 - 1).floor();
+        let top = (position.y - self.collision_mask.y as i32 / 2).floor();
+        let bottom = (position.y + self.collision_mask.y as i32 / 2 - 1).floor();
+
+        // Rounded down rather than towards zero so the pixels just outside the level's left and
+        // top edges land in the tiles outside it
+        for x in left.div_euclid(8)..=right.div_euclid(8) {
+            for y in top.div_euclid(8)..=bottom.div_euclid(8) {
+                let area = pixel_area(left - x * 8, right - x * 8, top - y * 8, bottom - y * 8);
+                if something_fn(x, y, area) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    pub fn collision_at_point(&self, level: &World, position: Vector2D<FixedNumberType>) -> bool {
+        self.something_at_point(position, |x, y, area| level.collides(x, y, area))
+    }
+
+    /// Whether moving to `position` runs into something solid. Platforms are only solid to
+    /// things coming down onto them, so anything already lower than a platform's top passes
+    /// through it
+    pub fn collision_moving_to(&self, level: &World, position: Vector2D<FixedNumberType>) -> bool {
+        self.collision_at_point(level, position) || self.lands_on_platform(level, position)
+    }
+
+    fn lands_on_platform(&self, level: &World, position: Vector2D<FixedNumberType>) -> bool {
+        if position.y <= self.position.y {
+            return false;
+        }
+
+        let feet = (self.position.y + self.collision_mask.y as i32 / 2 - 1).floor();
+        self.something_at_point(position, |x, y, area| {
+            y * 8 > feet && level.at_point(x, y, tilemap::PLATFORM_TILE, area)
+        })
+    }
+
+    pub fn killision_at_point(&self, level: &World, position: Vector2D<FixedNumberType>) -> bool {
+        self.something_at_point(position, |x, y, area| level.kills(x, y, area))
+    }
+
+    pub fn completion_at_point(&self, level: &World, position: Vector2D<FixedNumberType>) -> bool {
+        self.something_at_point(position, |x, y, area| level.wins(x, y, area))
+    }
+
+    /// All the tile flags of every tile under the collision mask
+    pub fn flags_at_point(&self, level: &World, position: Vector2D<FixedNumberType>) -> u32 {
+        let mut flags = 0;
+        self.something_at_point(position, |x, y, _| {
+            flags |= level.flags_at(x, y);
+            false
+        });
+        flags
+    }
+
+    /// All the tile flags of the row of tiles directly below the collision mask
+    pub fn flags_under_feet(&self, level: &World) -> u32 {
+        let left = (self.position.x - self.collision_mask.x as i32 / 2)
+            .floor()
+            .div_euclid(8);
+        let right = (self.position.x + self.collision_mask.x as i32 / 2 - 1)
+            .floor()
+            .div_euclid(8);
+        let below = (self.position.y + self.collision_mask.y as i32 / 2)
+            .floor()
+            .div_euclid(8);
+
+        (left..=right).fold(0, |flags, x| flags | level.flags_at(x, below))
+    }
+
+    /// How much velocity is kept each frame on the surface being stood on, out of 64
+    pub fn ground_friction(&self, level: &World) -> i32 {
+        if self.flags_under_feet(level) & tilemap::ICE_TILE != 0 {
+            ICE_FRICTION
+        } else {
+            GROUND_FRICTION
+        }
+    }
+
+    /// Moves the entity along with any conveyor it is standing on. This is kept apart from
+    /// `velocity` so standing on a conveyor doesn't build up speed
+    pub fn ride_conveyor(&mut self, level: &World) {
+        let flags = self.flags_under_feet(level);
+        if flags & tilemap::CONVEYOR_TILE == 0 {
+            return;
+        }
+
+        let belt_velocity: FixedNumberType = if flags & tilemap::CONVEYOR_LEFT_TILE != 0 {
+            (-CONVEYOR_VELOCITY).into()
+        } else {
+            CONVEYOR_VELOCITY.into()
+        };
+
+        let carried = (belt_velocity, 0.into()).into();
+        if !self.collision_at_point(level, self.position + carried) {
+            self.position += carried;
+        } else {
+            self.position += self.binary_search_collision(level, (1, 0).into(), belt_velocity);
+        }
+    }
+
+    // fn enemy_collision_at_point(
+    //     &self,
+    //     enemies: &[enemies::Enemy],
+    //     position: Vector2D<FixedNumberType>,
+    // ) -> bool {
+    //     for enemy in enemies {
+    //         if enemy.collides_with_hat(position) {
+    //             return true;
+    //         }
+    //     }
+    //     false
+    // }
+
+    // returns the distance actually moved
+    pub fn update_position(&mut self, level: &World) -> Vector2D<FixedNumberType> {
+        let old_position = self.position;
+        let x_velocity = (self.velocity.x, 0.into()).into();
+        if !self.collision_at_point(level, self.position + x_velocity) {
+            self.position += x_velocity;
+        } else {
+            self.position += self.binary_search_collision(level, (1, 0).into(), self.velocity.x);
+        }
+
+        let y_velocity = (0.into(), self.velocity.y).into();
+        if !self.collision_moving_to(level, self.position + y_velocity) {
+            self.position += y_velocity;
+        } else {
+            self.position += self.binary_search_collision(level, (0, 1).into(), self.velocity.y);
+        }
+        self.position - old_position
+    }
+
+    // fn update_position_with_enemy(
+    //     &mut self,
+    //     level: &World,
+    //     enemies: &[enemies::Enemy],
+    // ) -> (Vector2D<FixedNumberType>, bool) {
+    //     let mut was_enemy_collision = false;
+    //     let old_position = self.position;
+    //     let x_velocity = (self.velocity.x, 0.into()).into();
+    //
+    //     if !(self.collision_at_point(level, self.position + x_velocity)
+    //         || self.enemy_collision_at_point(enemies, self.position + x_velocity))
+    //     {
+    //         self.position += x_velocity;
+    //     } else if self.enemy_collision_at_point(enemies, self.position + x_velocity) {
+    //         self.position -= x_velocity;
+    //         was_enemy_collision = true;
+    //     }
+    //
+    //     let y_velocity = (0.into(), self.velocity.y).into();
+    //     if !(self.collision_at_point(level, self.position + y_velocity)
+    //         || self.enemy_collision_at_point(enemies, self.position + y_velocity))
+    //     {
+    //         self.position += y_velocity;
+    //     } else if self.enemy_collision_at_point(enemies, self.position + y_velocity) {
+    //         self.position -= y_velocity;
+    //         was_enemy_collision = true;
+    //     }
+    //
+    //     (self.position - old_position, was_enemy_collision)
+    // }
+
+    fn binary_search_collision(
+        &self,
+        level: &World,
+        unit_vector: Vector2D<FixedNumberType>,
+        initial: FixedNumberType,
+    ) -> Vector2D<FixedNumberType> {
+        let mut low: FixedNumberType = 0.into();
+        let mut high = initial;
+
+        let one: FixedNumberType = 1.into();
+        while (high - low).abs() > one / 8 {
+            let mid = (low + high) / 2;
+            let new_vel: Vector2D<FixedNumberType> = unit_vector * mid;
+
+            if self.collision_moving_to(level, self.position + new_vel) {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+
+        unit_vector * low
+    }
+
+    pub fn commit_position(&mut self, additional_offset: Vector2D<FixedNumberType>) {
+        let position = (self.position - additional_offset).floor() - self.sprite_offset;
+        self.sprite.set_position(position - self.size);
+        if position.x < -self.size.x
+            || position.x > SCREEN_WIDTH + self.size.x
+            || position.y < -self.size.y
+            || position.y > SCREEN_HEIGHT + self.size.y
+        {
+            self.sprite.hide();
+        } else {
+            self.sprite.show();
+        }
+    }
+}
+
+/// Builds an 8x8 tile hit mask covering the inclusive pixel ranges given,
+/// which are relative to the tile and get clamped to it
+pub fn pixel_area(left: i32, right: i32, top: i32, bottom: i32) -> u64 {
+    let (x_start, x_end) = (left.clamp(0, 7), right.clamp(0, 7));
+    let (y_start, y_end) = (top.clamp(0, 7), bottom.clamp(0, 7));
+
+    let row = (0xffu64 >> (7 - x_end)) & (0xffu64 << x_start);
+    let mut area = 0;
+    for y in y_start..=y_end {
+        area |= row << (y * 8);
+    }
+    area
+}
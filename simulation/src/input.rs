@@ -0,0 +1,130 @@
+use alloc::boxed::Box;
+
+/// Bits for each button, the same as agb's `input::Button`
+pub mod button {
+    pub const A: u16 = 1 << 0;
+    pub const B: u16 = 1 << 1;
+    pub const SELECT: u16 = 1 << 2;
+    pub const START: u16 = 1 << 3;
+    pub const RIGHT: u16 = 1 << 4;
+    pub const LEFT: u16 = 1 << 5;
+    pub const UP: u16 = 1 << 6;
+    pub const DOWN: u16 = 1 << 7;
+    pub const R: u16 = 1 << 8;
+    pub const L: u16 = 1 << 9;
+}
+
+/// Where the buttons held each frame come from, the GBA's buttons or a [Replay] of a
+/// recording
+///
+/// [Replay]: crate::replay::Replay
+pub trait InputSource {
+    /// The [button] bits held this frame, called once for every frame the simulation runs
+    fn next_frame(&mut self) -> u16;
+}
+
+impl<I: InputSource + ?Sized> InputSource for &mut I {
+    fn next_frame(&mut self) -> u16 {
+        (**self).next_frame()
+    }
+}
+
+impl<I: InputSource + ?Sized> InputSource for Box<I> {
+    fn next_frame(&mut self) -> u16 {
+        (**self).next_frame()
+    }
+}
+
+/// A direction on one axis of the d-pad
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tri {
+    /// Right or down
+    Positive = 1,
+    /// Unpressed
+    Zero = 0,
+    /// Left or up
+    Negative = -1,
+}
+
+impl Tri {
+    fn from_buttons(negative: bool, positive: bool) -> Self {
+        match (negative, positive) {
+            (true, false) => Tri::Negative,
+            (false, true) => Tri::Positive,
+            _ => Tri::Zero,
+        }
+    }
+}
+
+/// The buttons held this frame and the last one, which is all the simulation knows about the
+/// player's input. Works the same way as agb's `ButtonController` but can be given buttons
+/// from anywhere
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Buttons {
+    previous: u16,
+    current: u16,
+}
+
+impl Buttons {
+    /// Moves on a frame with the [button] bits now held
+    pub fn update(&mut self, pressed: u16) {
+        self.previous = self.current;
+        self.current = pressed;
+    }
+
+    /// The [button] bits held this frame
+    pub fn pressed(&self) -> u16 {
+        self.current
+    }
+
+    /// Whether any of the `buttons` are held
+    pub fn is_pressed(&self, buttons: u16) -> bool {
+        self.current & buttons != 0
+    }
+
+    /// Whether any of the `buttons` are held this frame but weren't last frame
+    pub fn is_just_pressed(&self, buttons: u16) -> bool {
+        self.current & !self.previous & buttons != 0
+    }
+
+    pub fn x_tri(&self) -> Tri {
+        Tri::from_buttons(
+            self.is_pressed(button::LEFT),
+            self.is_pressed(button::RIGHT),
+        )
+    }
+
+    pub fn y_tri(&self) -> Tri {
+        Tri::from_buttons(self.is_pressed(button::UP), self.is_pressed(button::DOWN))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buttons_are_only_just_pressed_on_the_first_frame() {
+        let mut buttons = Buttons::default();
+        buttons.update(button::A);
+        assert!(buttons.is_just_pressed(button::A));
+
+        buttons.update(button::A | button::B);
+        assert!(!buttons.is_just_pressed(button::A));
+        assert!(buttons.is_just_pressed(button::B));
+        assert!(buttons.is_pressed(button::A));
+    }
+
+    #[test]
+    fn opposite_directions_cancel_out() {
+        let mut buttons = Buttons::default();
+        buttons.update(button::LEFT | button::DOWN);
+        assert_eq!(
+            (buttons.x_tri(), buttons.y_tri()),
+            (Tri::Negative, Tri::Positive)
+        );
+
+        buttons.update(button::LEFT | button::RIGHT);
+        assert_eq!(buttons.x_tri(), Tri::Zero);
+    }
+}
@@ -0,0 +1,48 @@
+//! Everything that happens while playing a level: where things are, how they move and collide,
+//! and what state the player and enemies are in.
+//!
+//! Nothing here depends on agb, so it is tested on the host with
+//! `cargo test-simulation --target <your host target>`. The game draws it through the
+//! [Sprite](sprite::Sprite) trait and feeds it [Buttons](input::Buttons) from the GBA's buttons.
+
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+pub mod camera;
+pub mod chunks;
+pub mod enemies;
+pub mod entity;
+pub mod input;
+pub mod player;
+pub mod replay;
+pub mod sprite;
+pub mod time_attack;
+pub mod types;
+pub mod world;
+
+/// Bit flags for tile behaviours, a tile can have any combination of them
+pub mod tilemap {
+    pub const COLLISION_TILE: u32 = 1;
+    pub const KILL_TILE: u32 = 2;
+    pub const WIN_TILE: u32 = 4;
+    pub const LADDER_TILE: u32 = 8;
+    pub const WATER_TILE: u32 = 16;
+    pub const PLATFORM_TILE: u32 = 32;
+    pub const ICE_TILE: u32 = 64;
+    pub const CONVEYOR_TILE: u32 = 128;
+    /// Set alongside `CONVEYOR_TILE` for conveyors moving left instead of right
+    pub const CONVEYOR_LEFT_TILE: u32 = 256;
+}
+
+/// Tiles in a level's layers are the tile's index in the layer's tile set with how it is
+/// flipped in the top bits
+pub mod map_tile {
+    pub const ID_MASK: u16 = (1 << 14) - 1;
+    pub const HFLIP: u16 = 1 << 14;
+    pub const VFLIP: u16 = 1 << 15;
+
+    pub const fn id(tile: u16) -> usize {
+        (tile & ID_MASK) as usize
+    }
+}
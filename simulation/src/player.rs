@@ -0,0 +1,408 @@
+use crate::entity::{Entity, GROUND_FRICTION};
+use crate::input::{button, Buttons, Tri};
+use crate::player::PlayerAction::{Climb, DoubleJump, Idle, Swim};
+use crate::sprite::{Animation, Sprite};
+use crate::tilemap;
+use crate::types::FixedNumberType;
+use crate::world::World;
+use agb_fixnum::Vector2D;
+
+const X_VELOCITY: i32 = 2;
+/// Upwards speed of a swim stroke in quarter pixels per frame
+const SWIM_STROKE_VELOCITY: i32 = 5;
+/// Climbing speed in half pixels per frame
+const CLIMB_VELOCITY: i32 = 1;
+
+#[derive(Debug, PartialEq)]
+pub enum PlayerAction {
+    Idle,
+    Run,
+    Dash,
+    Jump,
+    DoubleJump,
+    Attack,
+    Climb,
+    Swim,
+}
+
+#[derive(Default)]
+pub struct AttackState {
+    pub attack_frame: usize,
+    pub times_last_attack_frame_displayed: i32,
+    pub animation_done: bool,
+    pub was_running: bool,
+}
+
+impl AttackState {
+    pub fn increment_attack_frame(&mut self, attack_sprite_len: usize) {
+        let times_to_show_frame = 4;
+
+        //Has already shown the attack frame max time so move to next frame
+        if self.times_last_attack_frame_displayed >= times_to_show_frame {
+            //IF it's the last frame then reset to 0
+            if self.attack_frame + 1 > attack_sprite_len {
+                //todo reset function
+                self.attack_frame = 0;
+                self.animation_done = true;
+                self.times_last_attack_frame_displayed = 0;
+            } else {
+                //Still more to go
+                self.attack_frame += 1;
+            }
+            self.times_last_attack_frame_displayed = 0;
+        } else {
+            //Still can show this frame a few more times
+            self.times_last_attack_frame_displayed += 1;
+        }
+    }
+}
+
+pub struct Player<S> {
+    pub warrior: Entity<S>,
+    pub hat_left_range: bool,
+    pub hat_slow_counter: i32,
+    pub warrior_frame: u8,
+    pub num_recalls: i8,
+    pub is_on_ground: bool,
+    pub facing: Tri,
+    pub last_idle_frame: i32,
+    /// Some sprites do not line up perfectly. Attack for instance does not
+    /// this lets you control it a bit without changing the Player's position
+    pub sprite_off_set: Vector2D<FixedNumberType>,
+    pub action: PlayerAction,
+    pub attack_state: AttackState,
+}
+
+impl<S: Sprite> Player<S> {
+    pub fn new(sprite: S, start_position: Vector2D<FixedNumberType>) -> Self {
+        let mut warrior = Entity::new(sprite, (16_u16, 16_u16).into(), None);
+        //
+        warrior.sprite.set_animation(Animation::WarriorIdle, 0);
+        warrior.position = start_position + (0, -7).into();
+
+        Player {
+            warrior,
+            hat_slow_counter: 0,
+            hat_left_range: false,
+            warrior_frame: 0,
+            num_recalls: 0,
+            is_on_ground: true,
+            facing: Tri::Zero,
+            last_idle_frame: 0,
+            sprite_off_set: (0, 0).into(),
+            action: PlayerAction::Idle,
+            attack_state: AttackState::default(),
+        }
+    }
+
+    pub fn update_frame(&mut self, input: &Buttons, timer: i32, level: &World) {
+        //TODO not sure how to do a double dash. Maybe count a few 0s before -1 or 1
+        //OR could just do longer. GUess i could do like holding down r or attack?
+
+        //     // throw or recall
+        //     if input.is_just_pressed(button::A) {
+        //         if self.hat_state == HatState::OnHead {
+        //             let direction: Vector2D<FixedNumberType> = {
+        //                 let up_down = input.y_tri() as i32;
+        //                 let left_right = if up_down == 0 {
+        //                     self.facing as i32
+        //                 } else {
+        //                     input.x_tri() as i32
+        //                 };]
+        //                 (left_right, up_down).into()
+        //             };
+        //
+        //             if direction != (0, 0).into() {
+        //                 let mut velocity = direction.normalise() * 5;
+        //                 if velocity.y > 0.into() {
+        //                     velocity.y *= FixedNumberType::new(4) / 3;
+        //                 }
+        //                 self.hat.velocity = velocity;
+        //                 self.hat_state = HatState::Thrown;
+        //
+        //                 sfx_player.throw();
+        //             }
+        //         } else if self.hat_state == HatState::Thrown {
+        //             self.num_recalls += 1;
+        //             if self.num_recalls < 3 {
+        //                 self.hat.velocity = (0, 0).into();
+        //                 self.warrior.velocity = (0, 0).into();
+        //                 self.hat_state = HatState::warriorTowards;
+        //             }
+        //         } else if self.hat_state == HatState::warriorTowards {
+        //             self.hat_state = HatState::Thrown;
+        //             self.warrior.velocity /= 8;
+        //         }
+        //     }
+        //
+        let tile_flags = self.warrior.flags_at_point(level, self.warrior.position);
+        let on_ladder_top = self.is_on_ladder_top(level);
+        let climbing_down = on_ladder_top && input.y_tri() == Tri::Positive;
+        let on_ladder = tile_flags & tilemap::LADDER_TILE != 0 || climbing_down;
+        let in_water = tile_flags & tilemap::WATER_TILE != 0;
+        if on_ladder
+            && (input.y_tri() == Tri::Negative || climbing_down)
+            && self.action != Climb
+            && self.action != PlayerAction::Attack
+        {
+            self.action = Climb;
+            self.sprite_off_set = (0, 0).into();
+            self.warrior.sprite.set_hflip(false);
+        }
+
+        if self.action == Climb {
+            if !on_ladder {
+                // Walked off the side of the ladder
+                self.end_climb(Idle);
+            } else if input.is_just_pressed(button::A) {
+                self.warrior.velocity.y = -FixedNumberType::new(3) / 2;
+                self.end_climb(PlayerAction::Jump);
+            } else {
+                self.update_climb(input, timer, level);
+                return;
+            }
+        }
+
+        let was_on_ground = self.is_on_ground;
+        let is_on_ground = on_ladder_top
+            || self
+                .warrior
+                .collision_moving_to(level, self.warrior.position + (0, 1).into());
+        if is_on_ground && !was_on_ground && self.warrior.velocity.y > 1.into() {
+            self.action = PlayerAction::Idle;
+        }
+        self.is_on_ground = is_on_ground;
+
+        if !in_water && self.action == Swim {
+            // Leaving the water counts as a jump so there is still a double jump left
+            self.action = PlayerAction::Jump;
+        }
+
+        if in_water {
+            self.update_swim(input);
+        } else if is_on_ground {
+            //On the ground
+            // Slippery ground is slower to speed up on so the top speed stays the same
+            let friction = self.warrior.ground_friction(level);
+            self.warrior.velocity.x += FixedNumberType::new(input.x_tri() as i32 * X_VELOCITY)
+                * (64 - friction)
+                / (16 * (64 - GROUND_FRICTION));
+            self.warrior.velocity = self.warrior.velocity * friction / 64;
+            // if self.action != PlayerAction::Attack {
+            //     self.warrior.velocity.x +=
+            //         FixedNumberType::new(input.x_tri() as i32 * X_VELOCITY) / 16;
+            //     self.warrior.velocity = self.warrior.velocity * 54 / 64;
+            // } else {
+            //     self.warrior.velocity = (0, 0).into();
+            // }
+            //Jump
+            if input.is_just_pressed(button::A) {
+                self.warrior.velocity.y = -FixedNumberType::new(3) / 2;
+                self.action = PlayerAction::Jump;
+            }
+        } else {
+            //Double jump
+            if self.action != DoubleJump && input.is_just_pressed(button::A) {
+                self.warrior.velocity.y = -FixedNumberType::new(3) / 2;
+                self.action = DoubleJump;
+            }
+            self.warrior.velocity.x += FixedNumberType::new(input.x_tri() as i32) / 64;
+            self.warrior.velocity = self.warrior.velocity * 63 / 64;
+            let gravity: Vector2D<FixedNumberType> = (0, 1).into();
+            let gravity = gravity / 16;
+            self.warrior.velocity += gravity;
+        }
+        self.warrior.velocity = self.warrior.update_position(level);
+        self.warrior.ride_conveyor(level);
+
+        //Running
+        if self.warrior.velocity.x.abs() > 0.into() {
+            let offset = (ping_pong(timer / 16, 4)) as usize;
+            self.warrior_frame = offset as u8;
+            if self.action != PlayerAction::Attack {
+                self.warrior
+                    .sprite
+                    .set_animation(Animation::WarriorRun, offset);
+            }
+            if self.action == PlayerAction::Idle {
+                self.action = PlayerAction::Run;
+            }
+        } else if self.action != PlayerAction::Attack && self.action != Swim {
+            self.action = PlayerAction::Idle;
+        }
+
+        // Set logic of jump sprite
+        if self.warrior.velocity.y < -FixedNumberType::new(1) / 16 {
+            // going up
+            self.warrior_frame = 5;
+            let offset = (timer / 16) as usize;
+            if self.action != PlayerAction::Attack {
+                self.warrior
+                    .sprite
+                    .set_animation(Animation::WarriorJump, offset);
+            }
+        } else if self.warrior.velocity.y > FixedNumberType::new(1) / 16 {
+            // going down
+            let _offset = if self.warrior.velocity.y * 2 > 3.into() {
+                (timer / 4) as usize
+            } else {
+                // Don't flap beard unless going quickly
+                0
+            };
+
+            self.warrior_frame = 0;
+            // Can set the sprite for falling here if we want one for up or down
+        }
+
+        if input.x_tri() != Tri::Zero {
+            self.facing = input.x_tri();
+        }
+        self.show_facing();
+
+        //Attack
+        if input.is_just_pressed(button::B) && self.is_on_ground {
+            if self.action != PlayerAction::Attack && self.facing == Tri::Positive {
+                self.sprite_off_set = (-16, 0).into();
+                // self.warrior.position = self.warrior.position - (16, 0).into();
+            }
+
+            self.attack_state.was_running = self.action == PlayerAction::Run;
+            self.action = PlayerAction::Attack;
+        }
+
+        if self.action == PlayerAction::Attack {
+            let attack_frames = self.warrior.sprite.frames(Animation::WarriorAttack);
+            self.attack_state.increment_attack_frame(attack_frames);
+            if self.attack_state.animation_done {
+                //Reset to false or it never gets reset
+                self.attack_state.animation_done = false;
+                self.action = PlayerAction::Idle;
+                if self.facing == Tri::Positive {
+                    self.sprite_off_set = (0, 0).into();
+                }
+            }
+            self.new_attack_frame();
+        }
+
+        if self.action == Idle && self.is_on_ground {
+            self.sprite_off_set = (0, 0).into();
+            self.action = PlayerAction::Idle;
+            self.new_idle_frame(timer);
+        }
+    }
+
+    /// Water slows everything down and sinks slowly, A can be pressed as often as wanted to
+    /// swim upwards
+    fn update_swim(&mut self, input: &Buttons) {
+        if self.action != PlayerAction::Attack {
+            self.action = Swim;
+        }
+
+        if input.is_just_pressed(button::A) {
+            self.warrior.velocity.y = -FixedNumberType::new(SWIM_STROKE_VELOCITY) / 4;
+        }
+
+        self.warrior.velocity.x += FixedNumberType::new(input.x_tri() as i32) / 64;
+        self.warrior.velocity = self.warrior.velocity * 56 / 64;
+        let gravity: Vector2D<FixedNumberType> = (0, 1).into();
+        let gravity = gravity / 64;
+        self.warrior.velocity += gravity;
+    }
+
+    /// Whether the player is stood on the top of a ladder, which holds them up like the ground
+    /// so they can get on and off it there
+    fn is_on_ladder_top(&self, level: &World) -> bool {
+        let below = (self.warrior.position.y + self.warrior.collision_mask.y as i32 / 2).floor();
+
+        below.rem_euclid(8) == 0
+            && self.warrior.flags_under_feet(level) & tilemap::LADDER_TILE != 0
+            && self.warrior.flags_at_point(level, self.warrior.position) & tilemap::LADDER_TILE == 0
+    }
+
+    /// Gravity is off while climbing and the d-pad moves freely. Climbing up past the top of
+    /// the ladder stands the player on it, level with the ledge it leads to
+    fn update_climb(&mut self, input: &Buttons, timer: i32, level: &World) {
+        let mut velocity: Vector2D<FixedNumberType> = (
+            FixedNumberType::new(input.x_tri() as i32 * CLIMB_VELOCITY) / 2,
+            FixedNumberType::new(input.y_tri() as i32 * CLIMB_VELOCITY) / 2,
+        )
+            .into();
+
+        let next_flags = self
+            .warrior
+            .flags_at_point(level, self.warrior.position + (0.into(), velocity.y).into());
+        if velocity.y < 0.into() && next_flags & tilemap::LADDER_TILE == 0 {
+            // Lift the player's feet to the top of the ladder tile they are on
+            let half_height = self.warrior.collision_mask.y as i32 / 2;
+            let feet = (self.warrior.position.y + half_height - 1).floor();
+            let top = (
+                self.warrior.position.x,
+                (feet.div_euclid(8) * 8 - half_height).into(),
+            )
+                .into();
+            if !self.warrior.collision_at_point(level, top) {
+                self.warrior.position = top;
+                self.warrior.velocity = (0, 0).into();
+                self.end_climb(Idle);
+                self.is_on_ground = true;
+                self.new_idle_frame(timer);
+                return;
+            }
+
+            velocity.y = 0.into();
+        }
+
+        self.warrior.velocity = velocity;
+        self.warrior.velocity = self.warrior.update_position(level);
+        self.is_on_ground = false;
+
+        if self.warrior.velocity.y != 0.into() {
+            self.warrior
+                .sprite
+                .set_animation(Animation::WarriorClimb, (timer / 16) as usize);
+        }
+    }
+
+    /// The climbing animation is drawn facing the ladder, so the sprite is only flipped back
+    /// to the way the player was facing once they get off it
+    fn end_climb(&mut self, action: PlayerAction) {
+        self.action = action;
+        self.show_facing();
+    }
+
+    fn show_facing(&mut self) {
+        match self.facing {
+            Tri::Negative => self.warrior.sprite.set_hflip(true),
+            Tri::Positive => self.warrior.sprite.set_hflip(false),
+            Tri::Zero => {}
+        }
+    }
+
+    fn new_idle_frame(&mut self, timer: i32) {
+        let offset = (timer / 32) as usize;
+        self.warrior
+            .sprite
+            .set_animation(Animation::WarriorIdle, offset);
+    }
+
+    fn new_attack_frame(&mut self) {
+        let animation = match self.attack_state.was_running {
+            true => Animation::WarriorRunAttack,
+            false => Animation::WarriorAttack,
+        };
+
+        self.warrior
+            .sprite
+            .set_animation(animation, self.attack_state.attack_frame);
+    }
+}
+
+fn ping_pong(i: i32, n: i32) -> i32 {
+    let cycle = 2 * (n - 1);
+    let i = i % cycle;
+    if i >= n {
+        cycle - i
+    } else {
+        i
+    }
+}
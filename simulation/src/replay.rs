@@ -0,0 +1,167 @@
+//! Recording the buttons held while playing a level and playing them back. The simulation
+//! only changes with its input, so replaying a recording from the start of the level it was
+//! made on does exactly the same thing again
+
+use crate::input::InputSource;
+use alloc::vec::Vec;
+
+/// The buttons held every frame of a play session, stored as runs of frames with the same
+/// buttons held so holding a direction for a long time takes up very little space
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Recording {
+    /// `(buttons, frames)` pairs in the order they were held
+    runs: Vec<(u16, u16)>,
+}
+
+impl Recording {
+    pub const fn new() -> Self {
+        Recording { runs: Vec::new() }
+    }
+
+    /// A recording made of the `(buttons, frames)` runs from [Recording::runs]
+    pub fn from_runs(runs: Vec<(u16, u16)>) -> Self {
+        Recording {
+            runs: runs.into_iter().filter(|&(_, frames)| frames > 0).collect(),
+        }
+    }
+
+    pub fn runs(&self) -> &[(u16, u16)] {
+        &self.runs
+    }
+
+    /// Adds a frame with the `buttons` held to the end
+    pub fn push(&mut self, buttons: u16) {
+        match self.runs.last_mut() {
+            Some((held, frames)) if *held == buttons && *frames < u16::MAX => *frames += 1,
+            _ => self.runs.push((buttons, 1)),
+        }
+    }
+
+    /// How many frames long the recording is
+    pub fn frames(&self) -> usize {
+        self.runs.iter().map(|&(_, frames)| frames as usize).sum()
+    }
+
+    /// Plays the recording back from the start
+    pub fn replay(&self) -> Replay<'_> {
+        Replay {
+            runs: &self.runs,
+            run: 0,
+            frame: 0,
+        }
+    }
+}
+
+/// Passes on the buttons from another input source, recording them as it goes
+pub struct Recorder<I> {
+    source: I,
+    recording: Recording,
+}
+
+impl<I> Recorder<I> {
+    pub fn new(source: I) -> Self {
+        Recorder {
+            source,
+            recording: Recording::default(),
+        }
+    }
+
+    /// Everything recorded so far
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    pub fn into_recording(self) -> Recording {
+        self.recording
+    }
+}
+
+impl<I: InputSource> InputSource for Recorder<I> {
+    fn next_frame(&mut self) -> u16 {
+        let buttons = self.source.next_frame();
+        self.recording.push(buttons);
+        buttons
+    }
+}
+
+/// Plays back a [Recording] a frame at a time. Once it runs out no buttons are held
+pub struct Replay<'a> {
+    runs: &'a [(u16, u16)],
+    run: usize,
+    /// Frames of the current run already played
+    frame: u16,
+}
+
+impl Replay<'_> {
+    /// Whether every frame of the recording has been played
+    pub fn is_finished(&self) -> bool {
+        self.run >= self.runs.len()
+    }
+}
+
+impl InputSource for Replay<'_> {
+    fn next_frame(&mut self) -> u16 {
+        let Some(&(buttons, frames)) = self.runs.get(self.run) else {
+            return 0;
+        };
+
+        self.frame += 1;
+        if self.frame >= frames {
+            self.run += 1;
+            self.frame = 0;
+        }
+        buttons
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::button;
+
+    #[test]
+    fn frames_with_the_same_buttons_are_stored_as_one_run() {
+        let mut recording = Recording::default();
+        for buttons in [0, 0, button::RIGHT, button::RIGHT, button::RIGHT, 0] {
+            recording.push(buttons);
+        }
+
+        assert_eq!(recording.runs(), &[(0, 2), (button::RIGHT, 3), (0, 1)]);
+        assert_eq!(recording.frames(), 6);
+    }
+
+    #[test]
+    fn long_runs_are_split_instead_of_overflowing() {
+        let mut recording = Recording::default();
+        for _ in 0..u16::MAX as usize + 2 {
+            recording.push(button::A);
+        }
+
+        assert_eq!(recording.runs(), &[(button::A, u16::MAX), (button::A, 2)]);
+    }
+
+    #[test]
+    fn a_replay_gives_back_what_was_recorded_then_nothing() {
+        let held = [button::A, button::A, 0, button::LEFT | button::B, 0];
+        let mut recording = Recording::default();
+        for buttons in held {
+            recording.push(buttons);
+        }
+
+        let mut replay = recording.replay();
+        let replayed: Vec<u16> = (0..held.len()).map(|_| replay.next_frame()).collect();
+        assert_eq!(replayed, held);
+        assert!(replay.is_finished());
+        assert_eq!(replay.next_frame(), 0);
+    }
+
+    #[test]
+    fn the_recorder_records_what_it_passes_on() {
+        let recording = Recording::from_runs(alloc::vec![(button::UP, 2), (0, 0), (button::A, 1)]);
+        let mut recorder = Recorder::new(recording.replay());
+
+        let passed_on: Vec<u16> = (0..3).map(|_| recorder.next_frame()).collect();
+        assert_eq!(passed_on, [button::UP, button::UP, button::A]);
+        assert_eq!(recorder.into_recording(), recording);
+    }
+}
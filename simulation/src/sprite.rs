@@ -0,0 +1,29 @@
+use agb_fixnum::Vector2D;
+
+/// Every animation the simulation shows
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Animation {
+    WarriorIdle,
+    WarriorRun,
+    WarriorRunAttack,
+    WarriorJump,
+    /// Reaching hand over hand, drawn the same whichever way the player faces
+    WarriorClimb,
+    WarriorAttack,
+    BoarIdle,
+    BoarRun,
+    BoarHit,
+}
+
+/// How an entity is shown. The game puts it on screen with an OAM object, tests can ignore it
+pub trait Sprite {
+    /// Shows the `frame`th frame of the animation, wrapping around past the end
+    fn set_animation(&mut self, animation: Animation, frame: usize);
+    /// How many frames the animation has
+    fn frames(&self, animation: Animation) -> usize;
+    fn set_hflip(&mut self, flip: bool);
+    /// Top left corner on screen
+    fn set_position(&mut self, position: Vector2D<i32>);
+    fn show(&mut self);
+    fn hide(&mut self);
+}
@@ -0,0 +1,183 @@
+//! Racing the best run of a level. Level time is counted in frames the simulation ran, so a
+//! best run's time is the length of its recording and its ghost finishes on the same frame
+
+use crate::replay::Recording;
+use alloc::vec::Vec;
+use core::fmt;
+
+const FRAMES_PER_SECOND: u32 = 60;
+/// Stored in place of the time when there is no best run
+const NO_RUN: u32 = u32::MAX;
+/// The time and number of runs
+const HEADER_LEN: usize = 6;
+const RUN_LEN: usize = 4;
+
+/// The fastest a level has been finished and how it was played
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BestRun {
+    pub frames: u32,
+    /// Empty when the run was too long to store, so there's a time to beat but no ghost
+    pub recording: Recording,
+}
+
+impl BestRun {
+    /// The best run made from the recording of a level being finished
+    pub fn new(recording: Recording) -> Self {
+        BestRun {
+            frames: recording.frames() as u32,
+            recording,
+        }
+    }
+
+    /// Whether finishing in `frames` frames beats this run
+    pub fn is_beaten_by(&self, frames: u32) -> bool {
+        frames < self.frames
+    }
+
+    /// Stores the run in at most `max_len` bytes, little endian, as the time, the number of
+    /// runs of buttons and then each `(buttons, frames)` run. The recording is left out when
+    /// it doesn't fit
+    pub fn to_bytes(&self, max_len: usize) -> Vec<u8> {
+        let runs = self.recording.runs();
+        let runs = if HEADER_LEN + runs.len() * RUN_LEN <= max_len {
+            runs
+        } else {
+            &[]
+        };
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + runs.len() * RUN_LEN);
+        bytes.extend_from_slice(&self.frames.to_le_bytes());
+        bytes.extend_from_slice(&(runs.len() as u16).to_le_bytes());
+        for &(buttons, frames) in runs {
+            bytes.extend_from_slice(&buttons.to_le_bytes());
+            bytes.extend_from_slice(&frames.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Bytes meaning there is no best run, for clearing where one would be stored
+    pub fn none_bytes() -> [u8; HEADER_LEN] {
+        let mut bytes = [0; HEADER_LEN];
+        bytes[..4].copy_from_slice(&NO_RUN.to_le_bytes());
+        bytes
+    }
+
+    /// Reads a run back from [BestRun::to_bytes], `None` if there isn't one or it is cut short
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let frames = u32::from_le_bytes(bytes.get(0..4)?.try_into().ok()?);
+        if frames == NO_RUN {
+            return None;
+        }
+
+        let run_count = u16::from_le_bytes(bytes.get(4..6)?.try_into().ok()?) as usize;
+        let run_bytes = bytes.get(HEADER_LEN..HEADER_LEN + run_count * RUN_LEN)?;
+        let runs = (0..run_bytes.len())
+            .step_by(RUN_LEN)
+            .map(|at| {
+                (
+                    u16::from_le_bytes([run_bytes[at], run_bytes[at + 1]]),
+                    u16::from_le_bytes([run_bytes[at + 2], run_bytes[at + 3]]),
+                )
+            })
+            .collect();
+
+        Some(BestRun {
+            frames,
+            recording: Recording::from_runs(runs),
+        })
+    }
+}
+
+/// A time in frames shown as minutes, seconds and hundredths
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Time(pub u32);
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.0 / FRAMES_PER_SECOND;
+        let hundredths = self.0 % FRAMES_PER_SECOND * 100 / FRAMES_PER_SECOND;
+        write!(f, "{}:{:02}.{:02}", seconds / 60, seconds % 60, hundredths)
+    }
+}
+
+/// How many frames behind a time to beat, like the best run or par time, a time is, negative
+/// when ahead of it. Shown in seconds with a sign
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Split(pub i32);
+
+impl Split {
+    pub fn new(frames: u32, to_beat: u32) -> Self {
+        Split(frames as i32 - to_beat as i32)
+    }
+}
+
+impl fmt::Display for Split {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { '-' } else { '+' };
+        let frames = self.0.unsigned_abs();
+        let hundredths = frames % FRAMES_PER_SECOND * 100 / FRAMES_PER_SECOND;
+        write!(f, "{sign}{}.{:02}", frames / FRAMES_PER_SECOND, hundredths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::button;
+    use alloc::format;
+
+    fn best_run() -> BestRun {
+        BestRun::new(Recording::from_runs(alloc::vec![
+            (button::RIGHT, 90),
+            (button::RIGHT | button::A, 1),
+            (0, 29),
+        ]))
+    }
+
+    #[test]
+    fn a_best_run_takes_as_long_as_its_recording() {
+        let run = best_run();
+
+        assert_eq!(run.frames, 120);
+        assert!(run.is_beaten_by(119));
+        assert!(!run.is_beaten_by(120));
+    }
+
+    #[test]
+    fn best_runs_are_read_back_the_same_as_they_were_stored() {
+        let run = best_run();
+
+        assert_eq!(BestRun::from_bytes(&run.to_bytes(64)), Some(run));
+    }
+
+    #[test]
+    fn only_the_time_is_stored_when_the_recording_does_not_fit() {
+        let bytes = best_run().to_bytes(12);
+
+        assert_eq!(bytes.len(), HEADER_LEN);
+        assert_eq!(
+            BestRun::from_bytes(&bytes),
+            Some(BestRun {
+                frames: 120,
+                recording: Recording::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn missing_and_cut_short_runs_are_not_read() {
+        assert_eq!(BestRun::from_bytes(&BestRun::none_bytes()), None);
+        assert_eq!(BestRun::from_bytes(&[]), None);
+
+        let bytes = best_run().to_bytes(64);
+        assert_eq!(BestRun::from_bytes(&bytes[..bytes.len() - 1]), None);
+    }
+
+    #[test]
+    fn times_and_splits_are_shown_in_seconds() {
+        assert_eq!(format!("{}", Time(0)), "0:00.00");
+        assert_eq!(format!("{}", Time(61 * 60 + 30)), "1:01.50");
+        assert_eq!(format!("{}", Split::new(150, 120)), "+0.50");
+        assert_eq!(format!("{}", Split::new(57, 120)), "-1.05");
+    }
+}
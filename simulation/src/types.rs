@@ -0,0 +1,8 @@
+use agb_fixnum::FixedNum;
+
+pub type FixedNumberType = FixedNum<10>;
+pub const TILE_SIZE: u32 = 8;
+
+/// The GBA's screen size in pixels
+pub const SCREEN_WIDTH: i32 = 240;
+pub const SCREEN_HEIGHT: i32 = 160;
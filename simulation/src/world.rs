@@ -0,0 +1,190 @@
+use crate::chunks::ChunkedLayer;
+use crate::tilemap;
+use agb_fixnum::Vector2D;
+
+/// The parts of a level the simulation needs, its collision and where everything starts
+pub struct World {
+    pub dimensions: Vector2D<u32>,
+    /// The index in `collision_tiles` of every tile of the level, chunked the same way as the
+    /// level's layers
+    pub collision: ChunkedLayer,
+    /// Each different pair of background and foreground `(flags, hit mask)` in the level. Hit
+    /// masks are 8x8, bit `y * 8 + x` is set where the tile's collision shape covers, and are
+    /// already flipped the way the tile is.
+    ///
+    /// Both layers can use any flag, a tile has the flags of either layer whose hit mask
+    /// overlaps the pixels being checked. Empty tiles have no flags on either layer
+    pub collision_tiles: &'static [[(u32, u64); 2]],
+    pub slimes: &'static [(i32, i32)],
+    pub boars: &'static [(i32, i32)],
+    pub snails: &'static [(i32, i32)],
+    pub enemy_stops: &'static [(i32, i32)],
+    /// Rectangles of `(x, y, width, height)` in pixels the camera stays inside while
+    /// the player is within them
+    pub camera_bounds: &'static [(i32, i32, i32, i32)],
+    pub start_pos: (i32, i32),
+}
+
+impl World {
+    pub fn collides(&self, x: i32, y: i32, area: u64) -> bool {
+        self.at_point(x, y, tilemap::COLLISION_TILE, area)
+    }
+
+    pub fn kills(&self, x: i32, y: i32, area: u64) -> bool {
+        self.at_point(x, y, tilemap::KILL_TILE, area)
+    }
+
+    pub fn wins(&self, x: i32, y: i32, area: u64) -> bool {
+        self.at_point(x, y, tilemap::WIN_TILE, area)
+    }
+
+    /// Checks if the tile at `x`, `y` has any of the `tile` flags where its hit mask overlaps
+    /// `area`, the pixels of the tile being tested in the same layout as the tile masks
+    pub fn at_point(&self, x: i32, y: i32, tile: u32, area: u64) -> bool {
+        self.flags_in_area(x, y, area) & tile != 0
+    }
+
+    /// All the flags of both layers at the tile `x`, `y`
+    pub fn flags_at(&self, x: i32, y: i32) -> u32 {
+        self.flags_in_area(x, y, u64::MAX)
+    }
+
+    /// Whether the tile `x`, `y` is inside the level
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        (0..self.dimensions.x as i32).contains(&x) && (0..self.dimensions.y as i32).contains(&y)
+    }
+
+    /// Outside the level the sides and top are walls so nothing can leave, but below it is a
+    /// pit that kills anything that falls in
+    fn flags_outside(&self, y: i32) -> u32 {
+        if y >= self.dimensions.y as i32 {
+            tilemap::KILL_TILE
+        } else {
+            tilemap::COLLISION_TILE
+        }
+    }
+
+    fn flags_in_area(&self, x: i32, y: i32, area: u64) -> u32 {
+        if !self.contains(x, y) {
+            return self.flags_outside(y);
+        }
+
+        // Anything missing from the grid is treated as an empty tile
+        let Some(tile) = self
+            .collision
+            .get(x as usize, y as usize)
+            .and_then(|tile| self.collision_tiles.get(tile as usize))
+        else {
+            return 0;
+        };
+
+        let mut flags = 0;
+        for (layer_flags, mask) in tile {
+            if mask & area != 0 {
+                flags |= layer_flags;
+            }
+        }
+        flags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::World;
+    use crate::chunks::{ChunkedLayer, CHUNK_SIZE};
+    use crate::tilemap::{COLLISION_TILE, KILL_TILE, WIN_TILE};
+    use agb_fixnum::Vector2D;
+
+    const TOP_HALF: u64 = 0x0000_0000_ffff_ffff;
+    const BOTTOM_HALF: u64 = 0xffff_ffff_0000_0000;
+
+    /// A level small enough to fit in one chunk with every tile using the first collision tile
+    fn empty_level(width: u32, height: u32) -> World {
+        test_level(width, height, one_chunk(&[]), &[[(0, 0); 2]])
+    }
+
+    /// A single chunk with `tiles` along its top, the rest of it is the first collision tile
+    fn one_chunk(tiles: &[u16]) -> ChunkedLayer {
+        let mut chunk = [0; CHUNK_SIZE * CHUNK_SIZE];
+        chunk[..tiles.len()].copy_from_slice(tiles);
+        ChunkedLayer {
+            width: 1,
+            chunk_map: &[0],
+            chunks: Vec::from([chunk]).leak(),
+        }
+    }
+
+    /// A level of `width` by `height` tiles with the given collision and nothing in it
+    fn test_level(
+        width: u32,
+        height: u32,
+        collision: ChunkedLayer,
+        collision_tiles: &'static [[(u32, u64); 2]],
+    ) -> World {
+        World {
+            dimensions: Vector2D::new(width, height),
+            collision,
+            collision_tiles,
+            slimes: &[],
+            boars: &[],
+            snails: &[],
+            enemy_stops: &[],
+            camera_bounds: &[],
+            start_pos: (0, 0),
+        }
+    }
+
+    #[test]
+    fn the_sides_and_top_of_the_level_are_walls() {
+        let level = empty_level(2, 2);
+
+        for (x, y) in [(-1, 0), (2, 1), (0, -1), (-1, -1), (2, -1)] {
+            assert_eq!(level.flags_at(x, y), COLLISION_TILE);
+        }
+    }
+
+    #[test]
+    fn below_the_level_kills_without_being_solid() {
+        let level = empty_level(2, 2);
+
+        for (x, y) in [(0, 2), (1, 5), (-1, 2), (2, 2)] {
+            assert_eq!(level.flags_at(x, y), KILL_TILE);
+            assert!(!level.collides(x, y, u64::MAX));
+        }
+        assert!(!level.wins(0, 2, u64::MAX));
+    }
+
+    #[test]
+    fn unknown_collision_tiles_are_empty() {
+        // The second tile's id is past the end of the collision tiles, the second chunk is past
+        // the end of the chunks and the bottom row of chunks is missing from the chunk map
+        let mut collision = one_chunk(&[0, 7]);
+        collision.width = 2;
+        collision.chunk_map = &[0, 5];
+        let level = test_level(8, 8, collision, &[[(COLLISION_TILE, u64::MAX), (0, 0)]]);
+
+        assert_eq!(level.flags_at(0, 0), COLLISION_TILE);
+        assert_eq!(level.flags_at(1, 0), 0);
+        assert_eq!(level.flags_at(4, 0), 0);
+        assert_eq!(level.flags_at(0, 4), 0);
+    }
+
+    #[test]
+    fn layers_only_count_where_their_masks_overlap() {
+        let level = test_level(
+            1,
+            1,
+            one_chunk(&[0]),
+            &[[
+                (COLLISION_TILE, TOP_HALF),
+                (KILL_TILE | WIN_TILE, BOTTOM_HALF),
+            ]],
+        );
+
+        assert!(level.collides(0, 0, TOP_HALF));
+        assert!(!level.kills(0, 0, TOP_HALF));
+        assert!(!level.collides(0, 0, BOTTOM_HALF));
+        assert!(level.kills(0, 0, BOTTOM_HALF) && level.wins(0, 0, BOTTOM_HALF));
+        assert_eq!(level.flags_at(0, 0), COLLISION_TILE | KILL_TILE | WIN_TILE);
+    }
+}
@@ -0,0 +1,568 @@
+use agb_fixnum::Vector2D;
+use simulation::camera::Camera;
+use simulation::chunks::{ChunkedLayer, CHUNK_SIZE};
+use simulation::enemies::{Enemy, EnemyUpdateState};
+use simulation::input::{button, Buttons, InputSource, Tri};
+use simulation::player::{Player, PlayerAction};
+use simulation::replay::{Recorder, Recording};
+use simulation::sprite::{Animation, Sprite};
+use simulation::tilemap::{
+    COLLISION_TILE, CONVEYOR_LEFT_TILE, CONVEYOR_TILE, ICE_TILE, KILL_TILE, LADDER_TILE,
+    PLATFORM_TILE, WATER_TILE, WIN_TILE,
+};
+use simulation::types::{FixedNumberType, SCREEN_HEIGHT, SCREEN_WIDTH};
+use simulation::world::World;
+
+/// Tests only look at positions and states so nothing is drawn
+struct NoSprite;
+
+impl Sprite for NoSprite {
+    fn set_animation(&mut self, _animation: Animation, _frame: usize) {}
+
+    fn frames(&self, _animation: Animation) -> usize {
+        4
+    }
+
+    fn set_hflip(&mut self, _flip: bool) {}
+    fn set_position(&mut self, _position: Vector2D<i32>) {}
+    fn show(&mut self) {}
+    fn hide(&mut self) {}
+}
+
+/// Remembers which way it was last flipped and ignores everything else
+#[derive(Default)]
+struct FlipSprite {
+    hflip: bool,
+}
+
+impl Sprite for FlipSprite {
+    fn set_animation(&mut self, _animation: Animation, _frame: usize) {}
+
+    fn frames(&self, _animation: Animation) -> usize {
+        4
+    }
+
+    fn set_hflip(&mut self, flip: bool) {
+        self.hflip = flip;
+    }
+
+    fn set_position(&mut self, _position: Vector2D<i32>) {}
+    fn show(&mut self) {}
+    fn hide(&mut self) {}
+}
+
+/// A level drawn a row of tiles at a time, `#` is solid, `X` kills, `W` wins, `H` is a ladder,
+/// `~` is water, `=` is ice, `>` and `<` are conveyors, `-` is a platform and anything else is
+/// empty
+fn world(rows: &[&str]) -> World {
+    let (width, height) = (rows[0].len(), rows.len());
+    let tile = |x: usize, y: usize| match rows.get(y).and_then(|row| row.as_bytes().get(x)) {
+        Some(b'#') => 1,
+        Some(b'X') => 2,
+        Some(b'W') => 3,
+        Some(b'H') => 4,
+        Some(b'~') => 5,
+        Some(b'=') => 6,
+        Some(b'>') => 7,
+        Some(b'<') => 8,
+        Some(b'-') => 9,
+        _ => 0,
+    };
+
+    // Every chunk is stored even when it repeats, the game's levels are the ones that share them
+    let chunk_width = width.div_ceil(CHUNK_SIZE);
+    let chunk_count = chunk_width * height.div_ceil(CHUNK_SIZE);
+    let chunks: Vec<_> = (0..chunk_count)
+        .map(|chunk| {
+            let (chunk_x, chunk_y) = (chunk % chunk_width, chunk / chunk_width);
+            core::array::from_fn(|index| {
+                tile(
+                    chunk_x * CHUNK_SIZE + index % CHUNK_SIZE,
+                    chunk_y * CHUNK_SIZE + index / CHUNK_SIZE,
+                )
+            })
+        })
+        .collect();
+    let chunk_map: Vec<u16> = (0..chunk_count as u16).collect();
+
+    World {
+        dimensions: Vector2D::new(width as u32, height as u32),
+        collision: ChunkedLayer {
+            width: chunk_width,
+            chunk_map: chunk_map.leak(),
+            chunks: chunks.leak(),
+        },
+        collision_tiles: &[
+            [(0, 0), (0, 0)],
+            [(COLLISION_TILE, u64::MAX), (0, 0)],
+            [(KILL_TILE, u64::MAX), (0, 0)],
+            [(WIN_TILE, u64::MAX), (0, 0)],
+            [(LADDER_TILE, u64::MAX), (0, 0)],
+            [(WATER_TILE, u64::MAX), (0, 0)],
+            [(COLLISION_TILE | ICE_TILE, u64::MAX), (0, 0)],
+            [(COLLISION_TILE | CONVEYOR_TILE, u64::MAX), (0, 0)],
+            [
+                (
+                    COLLISION_TILE | CONVEYOR_TILE | CONVEYOR_LEFT_TILE,
+                    u64::MAX,
+                ),
+                (0, 0),
+            ],
+            [(PLATFORM_TILE, u64::MAX), (0, 0)],
+        ],
+        slimes: &[],
+        boars: &[],
+        snails: &[],
+        enemy_stops: &[],
+        camera_bounds: &[],
+        start_pos: (0, 0),
+    }
+}
+
+/// Ten tiles wide with the ground on the bottom row, which is 56 pixels down
+const FLAT: &[&str] = &[
+    "..........",
+    "..........",
+    "..........",
+    "..........",
+    "..........",
+    "..........",
+    "..........",
+    "##########",
+];
+const GROUND: i32 = 56;
+
+fn player_at(x: i32, y: i32) -> Player<NoSprite> {
+    Player::new(NoSprite, (x, y).into())
+}
+
+/// Runs the player for `frames` frames holding `held`
+fn run<S: Sprite>(player: &mut Player<S>, world: &World, held: u16, frames: i32) {
+    let mut buttons = Buttons::default();
+    for timer in 0..frames {
+        buttons.update(held);
+        player.update_frame(&buttons, timer, world);
+    }
+}
+
+fn is_standing(player: &Player<NoSprite>, world: &World) -> bool {
+    let position = player.warrior.position;
+    !player.warrior.collision_at_point(world, position)
+        && player
+            .warrior
+            .collision_moving_to(world, position + (0, 1).into())
+}
+
+#[test]
+fn falls_onto_the_ground() {
+    let world = world(FLAT);
+    // High up but all inside the level, above it is a wall
+    let mut player = player_at(40, 16);
+
+    run(&mut player, &world, 0, 120);
+
+    assert!(player.is_on_ground);
+    assert!(is_standing(&player, &world));
+    assert_eq!((player.warrior.position.y + 8).floor(), GROUND);
+}
+
+#[test]
+fn walls_stop_the_player() {
+    let world = world(&["..........", "......#...", "......#...", "##########"]);
+    let mut player = player_at(16, 23);
+
+    run(&mut player, &world, button::RIGHT, 200);
+
+    // The wall starts at 48 and the player is 16 pixels wide
+    let right = (player.warrior.position.x + 8).floor();
+    assert!((47..=48).contains(&right), "player got to {right}");
+    assert_eq!(player.facing, Tri::Positive);
+}
+
+#[test]
+fn the_left_edge_of_the_level_is_a_wall() {
+    let world = world(FLAT);
+    let mut player = player_at(24, GROUND - 1);
+
+    run(&mut player, &world, button::LEFT, 120);
+
+    // Not a pixel of the 16 pixel wide player gets past the edge
+    let x = player.warrior.position.x.floor();
+    assert!(x >= 8, "player got to {x}");
+}
+
+#[test]
+fn the_top_of_the_level_is_a_ceiling() {
+    // Only two tiles of room above the ground, less than a jump goes up
+    let world = world(&["..........", "..........", "##########"]);
+    let mut player = player_at(40, 15);
+
+    let mut buttons = Buttons::default();
+    let mut highest = player.warrior.position.y;
+    for timer in 0..60 {
+        buttons.update(if timer == 0 { button::A } else { 0 });
+        player.update_frame(&buttons, timer, &world);
+        highest = highest.min(player.warrior.position.y);
+    }
+
+    assert!(highest.floor() >= 8, "player got up to {highest}");
+}
+
+#[test]
+fn jumping_goes_up_and_comes_back_down() {
+    let world = world(FLAT);
+    let mut player = player_at(40, GROUND - 1);
+    let start = player.warrior.position;
+
+    let mut buttons = Buttons::default();
+    let mut highest = start.y;
+    for timer in 0..120 {
+        buttons.update(if timer == 0 { button::A } else { 0 });
+        player.update_frame(&buttons, timer, &world);
+        highest = highest.min(player.warrior.position.y);
+    }
+
+    assert!(highest < start.y - 8, "only got up to {highest}");
+    assert!(is_standing(&player, &world));
+    assert_eq!(player.warrior.position.y.floor(), start.y.floor());
+}
+
+/// `FLAT` filled with water up to the top
+const POOL: &[&str] = &[
+    "~~~~~~~~~~",
+    "~~~~~~~~~~",
+    "~~~~~~~~~~",
+    "~~~~~~~~~~",
+    "~~~~~~~~~~",
+    "~~~~~~~~~~",
+    "~~~~~~~~~~",
+    "##########",
+];
+
+#[test]
+fn water_sinks_slower_than_falling() {
+    let (air, water) = (world(FLAT), world(POOL));
+    let mut falling = player_at(40, 16);
+    let mut sinking = player_at(40, 16);
+    let start = sinking.warrior.position.y;
+
+    run(&mut falling, &air, 0, 20);
+    run(&mut sinking, &water, 0, 20);
+
+    assert_eq!(sinking.action, PlayerAction::Swim);
+    assert!(sinking.warrior.position.y > start, "didn't sink at all");
+    assert!(sinking.warrior.position.y < falling.warrior.position.y);
+
+    // Buoyancy holds the sinking speed down to an eighth of a pixel a frame
+    run(&mut sinking, &water, 0, 60);
+    let most: FixedNumberType = FixedNumberType::new(1) / 8;
+    assert!(
+        sinking.warrior.velocity.y <= most,
+        "sinking at {}",
+        sinking.warrior.velocity.y
+    );
+}
+
+#[test]
+fn a_swim_stroke_goes_up() {
+    let world = world(POOL);
+    let mut player = player_at(40, 32);
+    run(&mut player, &world, 0, 30);
+    let start = player.warrior.position.y;
+
+    let mut buttons = Buttons::default();
+    for timer in 0..10 {
+        buttons.update(if timer == 0 { button::A } else { 0 });
+        player.update_frame(&buttons, timer, &world);
+    }
+
+    assert!(
+        player.warrior.position.y < start - 4,
+        "only got up to {}",
+        player.warrior.position.y
+    );
+    assert_eq!(player.action, PlayerAction::Swim);
+}
+
+#[test]
+fn swimming_is_slower_than_walking() {
+    let (ground, water) = (world(FLAT), world(POOL));
+    let mut walking = player_at(16, GROUND - 1);
+    let mut swimming = player_at(16, GROUND - 1);
+
+    run(&mut walking, &ground, button::RIGHT, 30);
+    run(&mut swimming, &water, button::RIGHT, 30);
+
+    let walked = walking.warrior.position.x - 16;
+    let swum = swimming.warrior.position.x - 16;
+    assert!(swum > 0.into(), "didn't swim anywhere");
+    assert!(swum * 2 < walked, "swam {swum} and walked {walked}");
+}
+
+#[test]
+fn ice_slides_further_than_the_ground() {
+    let mut ice_rows = FLAT.to_vec();
+    *ice_rows.last_mut().unwrap() = "==========";
+    let (ground, ice) = (world(FLAT), world(&ice_rows));
+
+    // How far the player slides after running and letting go
+    let slide = |world: &World| {
+        let mut player = player_at(16, GROUND - 1);
+        run(&mut player, world, button::RIGHT, 20);
+        let let_go = player.warrior.position.x;
+        run(&mut player, world, 0, 60);
+        player.warrior.position.x - let_go
+    };
+
+    let (on_ground, on_ice) = (slide(&ground), slide(&ice));
+    assert!(
+        on_ice > on_ground * 2,
+        "slid {on_ice} on ice and {on_ground} on the ground"
+    );
+}
+
+#[test]
+fn conveyors_carry_the_player_the_way_they_move() {
+    let mut rightwards = FLAT.to_vec();
+    *rightwards.last_mut().unwrap() = ">>>>>>>>>>";
+    let mut leftwards = FLAT.to_vec();
+    *leftwards.last_mut().unwrap() = "<<<<<<<<<<";
+
+    let mut player = player_at(40, GROUND - 1);
+    run(&mut player, &world(&rightwards), 0, 10);
+    assert_eq!(player.warrior.position.x.floor(), 50);
+
+    let mut player = player_at(40, GROUND - 1);
+    run(&mut player, &world(&leftwards), 0, 10);
+    assert_eq!(player.warrior.position.x.floor(), 30);
+}
+
+#[test]
+fn platforms_can_be_jumped_up_through_and_stood_on() {
+    // The platform's top is 16 pixels above the ground, less than a jump goes up
+    let world = world(&[
+        "..........",
+        "..........",
+        "..........",
+        "..........",
+        "..........",
+        "..--------",
+        "..........",
+        "##########",
+    ]);
+    let mut player = player_at(40, GROUND - 1);
+
+    let mut buttons = Buttons::default();
+    for timer in 0..120 {
+        buttons.update(if timer == 0 { button::A } else { 0 });
+        player.update_frame(&buttons, timer, &world);
+    }
+
+    assert!(player.is_on_ground);
+    assert_eq!((player.warrior.position.y + 8).floor(), 40);
+
+    // Walking off the end drops back down to the ground
+    run(&mut player, &world, button::LEFT, 60);
+    run(&mut player, &world, 0, 60);
+    assert!(is_standing(&player, &world));
+    assert_eq!((player.warrior.position.y + 8).floor(), GROUND);
+}
+
+#[test]
+fn climbing_to_the_top_of_a_ladder_stands_on_the_ledge() {
+    // The ladder's top is level with the ledge, 24 pixels down
+    let world = world(&[
+        "..........",
+        "..........",
+        "..........",
+        "....H.####",
+        "....H.....",
+        "....H.....",
+        "....H.....",
+        "##########",
+    ]);
+    let mut player = player_at(36, GROUND - 1);
+
+    run(&mut player, &world, button::UP, 120);
+
+    assert_eq!(player.action, PlayerAction::Idle);
+    assert!(player.is_on_ground);
+    assert_eq!((player.warrior.position.y + 8).floor(), 24);
+
+    // Gravity is back on but the top of the ladder holds the player up
+    run(&mut player, &world, 0, 60);
+    assert_eq!((player.warrior.position.y + 8).floor(), 24);
+
+    run(&mut player, &world, button::RIGHT, 60);
+    assert!(is_standing(&player, &world));
+    assert_eq!((player.warrior.position.y + 8).floor(), 24);
+}
+
+#[test]
+fn climbing_faces_the_ladder_then_the_way_the_player_was_facing() {
+    let world = world(&[
+        "..........",
+        "..........",
+        "..........",
+        "....H.####",
+        "....H.....",
+        "....H.....",
+        "....H.....",
+        "##########",
+    ]);
+    let mut player = Player::new(FlipSprite::default(), (37, GROUND - 1).into());
+
+    run(&mut player, &world, button::LEFT, 1);
+    assert!(player.warrior.sprite.hflip);
+
+    run(&mut player, &world, button::UP, 10);
+    assert_eq!(player.action, PlayerAction::Climb);
+    assert!(!player.warrior.sprite.hflip);
+
+    run(&mut player, &world, button::UP, 120);
+    assert_eq!(player.action, PlayerAction::Idle);
+    assert_eq!(player.facing, Tri::Negative);
+    assert!(player.warrior.sprite.hflip);
+}
+
+/// A boar stood on the ground of `FLAT` at `x`
+fn boar_at(x: i32) -> Enemy<NoSprite> {
+    let position: Vector2D<FixedNumberType> = (x, GROUND - 7).into();
+    Enemy::new_boar(NoSprite, position)
+}
+
+#[test]
+fn a_boar_runs_at_and_kills_a_player_standing_still() {
+    let world = world(FLAT);
+    let mut player = player_at(16, GROUND - 1);
+    let mut boar = boar_at(56);
+
+    let mut buttons = Buttons::default();
+    let killed_on = (0..600).find(|&timer| {
+        buttons.update(0);
+        player.update_frame(&buttons, timer, &world);
+        matches!(
+            boar.update(&world, player.warrior.position, &player.action, timer),
+            EnemyUpdateState::KillPlayer
+        )
+    });
+
+    assert!(killed_on.is_some(), "the boar never reached the player");
+    assert_eq!(player.action, PlayerAction::Idle);
+}
+
+#[test]
+fn attacking_a_boar_kills_it() {
+    let world = world(FLAT);
+    let mut player = player_at(40, GROUND - 1);
+    let mut boar = boar_at(50);
+
+    let mut buttons = Buttons::default();
+    buttons.update(button::B);
+    player.update_frame(&buttons, 0, &world);
+    assert_eq!(player.action, PlayerAction::Attack);
+
+    assert!(matches!(
+        boar.update(&world, player.warrior.position, &player.action, 0),
+        EnemyUpdateState::Killed
+    ));
+
+    // Once its death animation is over the boar is gone
+    for timer in 1..30 {
+        boar.update(&world, player.warrior.position, &player.action, timer);
+    }
+    assert!(matches!(boar, Enemy::Empty));
+}
+
+#[test]
+fn boars_stay_out_of_the_water() {
+    // The boar is close enough to go for the player but the water is in the way
+    let world = world(&[
+        "............",
+        "............",
+        "............",
+        "............",
+        "............",
+        ".....~~.....",
+        ".....~~.....",
+        "############",
+    ]);
+    let mut player = player_at(16, GROUND - 1);
+    let mut boar = boar_at(76);
+
+    let mut buttons = Buttons::default();
+    for timer in 0..600 {
+        buttons.update(0);
+        player.update_frame(&buttons, timer, &world);
+        let state = boar.update(&world, player.warrior.position, &player.action, timer);
+        assert!(
+            !matches!(state, EnemyUpdateState::KillPlayer),
+            "the boar swam over"
+        );
+    }
+
+    assert!(matches!(boar, Enemy::Boar(_)));
+}
+
+#[test]
+fn the_camera_stays_inside_the_level() {
+    let row = ".".repeat(60);
+    let rows = vec![row.as_str(); 30];
+    let world = world(&rows);
+    let (width, height) = (60 * 8, 30 * 8);
+
+    let top_left = Camera::new(&world, (8, 8).into());
+    assert_eq!(top_left.position.floor(), (0, 0).into());
+
+    let bottom_right = Camera::new(&world, (width - 8, height - 8).into());
+    assert_eq!(
+        bottom_right.position.floor(),
+        (width - SCREEN_WIDTH, height - SCREEN_HEIGHT).into()
+    );
+
+    let mut camera = Camera::new(&world, (width / 2, height / 2).into());
+    for _ in 0..120 {
+        let position = camera.update(&world, (width - 8, 8).into(), Tri::Positive, true);
+        assert!((0..=width - SCREEN_WIDTH).contains(&position.x.floor()));
+        assert!((0..=height - SCREEN_HEIGHT).contains(&position.y.floor()));
+    }
+}
+
+#[test]
+fn replaying_a_recording_ends_up_in_the_same_place() {
+    let world = world(&[
+        "....................",
+        "....................",
+        "..........#.........",
+        "....................",
+        "####################",
+    ]);
+    let script = Recording::from_runs(vec![
+        (button::RIGHT, 30),
+        (button::RIGHT | button::A, 1),
+        (button::RIGHT, 12),
+        (button::RIGHT | button::A, 1),
+        (button::LEFT, 40),
+        (button::B, 1),
+        (0, 30),
+    ]);
+
+    let play = |input: &mut dyn InputSource| {
+        let mut player = player_at(16, 31);
+        let mut buttons = Buttons::default();
+        (0..script.frames() as i32)
+            .map(|timer| {
+                buttons.update(input.next_frame());
+                player.update_frame(&buttons, timer, &world);
+                player.warrior.position
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut recorder = Recorder::new(script.replay());
+    let played = play(&mut recorder);
+    let recording = recorder.into_recording();
+    assert_eq!(recording, script);
+
+    assert_eq!(play(&mut recording.replay()), played);
+}
@@ -1,2 +0,0 @@
-pub mod enemies;
-pub mod entity;
@@ -0,0 +1,41 @@
+use agb::input::{Button, ButtonController};
+use simulation::input::InputSource;
+
+/// The buttons being held on the GBA
+pub struct LiveInput {
+    controller: ButtonController,
+}
+
+impl LiveInput {
+    pub fn new() -> Self {
+        LiveInput {
+            controller: ButtonController::new(),
+        }
+    }
+}
+
+impl InputSource for LiveInput {
+    fn next_frame(&mut self) -> u16 {
+        self.controller.update();
+        pressed_buttons(&self.controller)
+    }
+}
+
+/// The [simulation::input::button] bits of the buttons held on the GBA
+fn pressed_buttons(input: &ButtonController) -> u16 {
+    [
+        Button::A,
+        Button::B,
+        Button::SELECT,
+        Button::START,
+        Button::RIGHT,
+        Button::LEFT,
+        Button::UP,
+        Button::DOWN,
+        Button::R,
+        Button::L,
+    ]
+    .into_iter()
+    .filter(|&button| input.is_pressed(button))
+    .fold(0, |pressed, button| pressed | button.bits() as u16)
+}
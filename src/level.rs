@@ -1,11 +1,19 @@
+use agb::display::palette16::Palette16;
 use agb::display::tiled::{TileSet, TileSetting};
-use agb::fixnum::Vector2D;
-use agb::println;
+use simulation::world::World;
+
+/// Layers are stored in chunks, the same way the level's collision is
+pub use simulation::chunks::ChunkedLayer;
 
 pub mod map_tiles {
     use super::Level;
     pub const LEVELS: &[Level] = &[level_one::get_level(), level_two::get_level()];
 
+    /// Tile set graphics for every level
+    pub mod assets {
+        include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+    }
+
     pub mod level_one {
         include!(concat!(env!("OUT_DIR"), "/level_1.rs"));
     }
@@ -14,24 +22,43 @@ pub mod map_tiles {
         include!(concat!(env!("OUT_DIR"), "/level_2.rs"));
     }
 
-    pub mod tilemap {
-        pub const COLLISION_TILE: i32 = 1;
-        pub const KILL_TILE: i32 = 2;
-        pub const WIN_TILE: i32 = 4;
+    pub mod map_tile {
+        use agb::display::tiled::TileSetting;
+        pub use simulation::map_tile::*;
+
+        /// The tile's setting from its tile set, flipped the way it is in the level. Tiles
+        /// past the end of the tile set are left blank
+        pub fn tile_setting(tile_settings: &[TileSetting], tile: u16) -> TileSetting {
+            tile_settings
+                .get(id(tile))
+                .copied()
+                .unwrap_or(TileSetting::BLANK)
+                .hflip(tile & HFLIP != 0)
+                .vflip(tile & VFLIP != 0)
+        }
     }
 }
 
 pub struct Level<'a> {
-    pub background: &'static [u16],
-    pub foreground: &'static [u16],
-    pub dimensions: Vector2D<u32>,
-    pub background_collision: &'static [u32],
-    pub foreground_collision: &'static [u32],
-    pub slimes: &'static [(i32, i32)],
-    pub boars: &'static [(i32, i32)],
-    pub snails: &'static [(i32, i32)],
-    pub enemy_stops: &'static [(i32, i32)],
-    pub start_pos: (i32, i32),
+    pub background: ChunkedLayer,
+    pub foreground: ChunkedLayer,
+    /// Collision and where everything starts, which is all the simulation needs
+    pub world: World,
+    /// The following come from the Tiled map's custom properties
+    pub name: &'static str,
+    pub subtitle: Option<&'static str>,
+    /// Shown under the level name while it loads
+    pub intro: Option<&'static str>,
+    /// Time to beat in frames
+    pub par_time: Option<i32>,
+    /// The tune the level asks for. Nothing plays music yet, so this is only carried through
+    /// from the map for when something does
+    #[allow(dead_code)]
+    pub music_id: Option<u32>,
+    /// GBA 15 bit colour shown behind everything
+    pub background_colour: u16,
+    /// The palettes of the level's tile sets
+    pub palettes: &'static [Palette16],
     pub background_tile_set: TileSet<'a>,
     pub background_tile_settings: &'static [TileSetting],
     pub foreground_tile_set: TileSet<'a>,
@@ -39,44 +66,100 @@ pub struct Level<'a> {
 }
 
 impl<'a> Level<'a> {
-    pub fn collides(&self, x: i32, y: i32) -> bool {
-        self.at_point(x, y, map_tiles::tilemap::COLLISION_TILE as u32)
+    /// The background tile at `x`, `y`, `None` outside the level which can be seen when the
+    /// level is smaller than the screen
+    pub fn background_tile(&self, x: i32, y: i32) -> Option<u16> {
+        self.world
+            .contains(x, y)
+            .then(|| self.background.tile(x as usize, y as usize))
     }
 
-    pub fn kills(&self, x: i32, y: i32) -> bool {
-        self.at_point(x, y, map_tiles::tilemap::KILL_TILE as u32)
+    /// The foreground tile at `x`, `y`, `None` outside the level
+    pub fn foreground_tile(&self, x: i32, y: i32) -> Option<u16> {
+        self.world
+            .contains(x, y)
+            .then(|| self.foreground.tile(x as usize, y as usize))
     }
+}
 
-    pub fn at_point(&self, x: i32, y: i32, tile: u32) -> bool {
-        if (x < 0 || x >= self.dimensions.x as i32) || (y < 0 || y >= self.dimensions.y as i32) {
-            return true;
-        }
-        let pos = (self.dimensions.x as i32 * y + x) as usize;
-        let tile_foreground = self.foreground[pos];
-        let tile_background = self.background[pos];
+#[cfg(test)]
+mod tests {
+    use super::map_tiles::{map_tile, LEVELS};
+    use agb::display::tiled::TileSetting;
+    use agb::timer::Divider;
+    use alloc::format;
+    use simulation::chunks::CHUNK_SIZE;
 
-        let foreground_tile_property = self.foreground_collision[tile_foreground as usize];
+    /// 228 lines of 1232 cycles
+    const CYCLES_PER_FRAME: u32 = 280_896;
+    /// Tiles on screen with the extra row and column shown while scrolling
+    const SCREEN_TILES: (i32, i32) = (31, 21);
 
-        let mut background_collision = false;
-        if tile_background <= self.background_collision.len() as u16 {
-            let background_tile_property = self.background_collision[tile_background as usize];
-            background_collision = background_tile_property == tile;
+    #[test_case]
+    fn tiles_past_the_end_of_the_tile_set_are_blank(_gba: &mut agb::Gba) {
+        let tile_settings = [TileSetting::new(1, false, false, 0)];
+
+        assert_eq!(
+            format!("{:?}", map_tile::tile_setting(&tile_settings, 5)),
+            format!("{:?}", TileSetting::BLANK)
+        );
+    }
+
+    #[test_case]
+    fn chunked_layers_match_the_level_size(_gba: &mut agb::Gba) {
+        for level in LEVELS {
+            for layer in [&level.background, &level.foreground, &level.world.collision] {
+                assert_eq!(
+                    layer.width,
+                    (level.world.dimensions.x as usize).div_ceil(CHUNK_SIZE)
+                );
+                assert_eq!(layer.chunk_map.len() % layer.width, 0);
+                assert!(layer
+                    .chunk_map
+                    .iter()
+                    .all(|&chunk| (chunk as usize) < layer.chunks.len()));
+            }
         }
-        let foreground_collision = foreground_tile_property == tile;
+    }
+
+    #[test_case]
+    fn looking_up_a_screen_of_tiles_fits_in_a_frame(gba: &mut agb::Gba) {
+        let mut timers = gba.timers.timers();
+        timers.timer3.set_overflow_amount(0).set_cascade(true);
+        timers.timer3.set_enabled(true);
+        timers
+            .timer2
+            .set_overflow_amount(0)
+            .set_divider(Divider::Divider1)
+            .set_enabled(true);
 
-        if background_collision {
-            println!("x: {}, y: {}", x, y);
-            println!("Background collision  ({}, {})", tile_background, tile);
+        // Both layers and the collision are looked up for every tile when the whole screen is
+        // drawn, which is far more than scrolling or moving things about needs in a frame
+        let mut tiles = 0u32;
+        for level in LEVELS {
+            for y in 0..SCREEN_TILES.1 {
+                for x in 0..SCREEN_TILES.0 {
+                    let background = level.background_tile(x, y).unwrap_or(0);
+                    let foreground = level.foreground_tile(x, y).unwrap_or(0);
+                    let flags = level.world.flags_at(x, y);
+                    tiles = tiles.wrapping_add(core::hint::black_box(
+                        (background ^ foreground) as u32 ^ flags,
+                    ));
+                }
+            }
         }
-        //
-        // if foreground_collision {
-        //     println!("Foreground collision  ({}, {})", tile_foreground, tile);
-        // }
 
-        foreground_collision || background_collision
-    }
+        timers.timer2.set_enabled(false);
+        let cycles = (u32::from(timers.timer3.value()) << 16) | u32::from(timers.timer2.value());
+        timers.timer3.set_enabled(false);
+        core::hint::black_box(tiles);
 
-    pub fn wins(&self, x: i32, y: i32) -> bool {
-        self.at_point(x, y, map_tiles::tilemap::WIN_TILE as u32)
+        let screens = LEVELS.len() as u32;
+        let lookups = screens * 3 * (SCREEN_TILES.0 * SCREEN_TILES.1) as u32;
+        agb::println!(
+            "{lookups} tile and collision lookups took {cycles} cycles, {} each",
+            cycles / lookups
+        );
+        assert!(cycles / screens < CYCLES_PER_FRAME);
     }
 }
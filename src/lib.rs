@@ -4,40 +4,99 @@
 #![cfg_attr(test, reexport_test_harness_main = "test_main")]
 #![cfg_attr(test, test_runner(agb::test_runner::test_runner))]
 
-mod display_text;
-mod entities;
+mod input;
 mod level;
+mod loading_screen;
 mod map;
-mod player;
 mod playing_level;
+mod save;
 mod splash_screen;
-mod types;
+mod split_timer;
+mod sprites;
+mod transition;
 
 extern crate alloc;
 
+use crate::input::LiveInput;
 use crate::level::map_tiles;
+use crate::level::map_tiles::map_tile;
+use crate::loading_screen::LoadingScreen;
 use crate::playing_level::{PlayingLevel, UpdateState};
+use crate::save::BestRuns;
+use crate::split_timer::SplitTimer;
+use crate::transition::{Transition, TransitionStyle};
 use agb::display::tiled::{
-    InfiniteScrolledMap, PartialUpdateStatus, RegularBackgroundSize, TileFormat, TiledMap,
+    InfiniteScrolledMap, PartialUpdateStatus, RegularBackgroundSize, TileFormat, Tiled0, TiledMap,
 };
 use agb::display::{Font, Priority};
 use agb::fixnum::Vector2D;
 use agb::include_font;
+use agb::input::{Button, ButtonController};
 use alloc::boxed::Box;
-use alloc::format;
+use simulation::time_attack::BestRun;
 
 const LEVEL_LOADING_SCREEN_WAIT: i32 = 5;
+/// Frames the results of a finished level are shown for before it fades out
+const RESULTS_WAIT: u32 = 90;
+
+// Tests are run in an emulator with `cargo test`, this is the test ROM's entry point
+#[cfg(test)]
+#[agb::entry]
+fn agb_test_main(_gba: agb::Gba) -> ! {
+    #[allow(clippy::empty_loop)]
+    loop {} // full implementation provided by the #[entry]
+}
+
+/// The scrolling background and foreground of `LEVELS[level]`
+pub(crate) fn level_layers<'a>(
+    tiled: &'a Tiled0<'_>,
+    level: usize,
+) -> (InfiniteScrolledMap<'a>, InfiniteScrolledMap<'a>) {
+    let background = InfiniteScrolledMap::new(
+        tiled.background(
+            Priority::P0,
+            RegularBackgroundSize::Background32x64,
+            TileFormat::FourBpp,
+        ),
+        Box::new(move |pos: Vector2D<i32>| {
+            let level = &map_tiles::LEVELS[level];
+            let tile = level.background_tile(pos.x, pos.y).unwrap_or(0);
+
+            (
+                &level.background_tile_set,
+                map_tile::tile_setting(level.background_tile_settings, tile),
+            )
+        }),
+    );
+    let foreground = InfiniteScrolledMap::new(
+        tiled.background(
+            Priority::P2,
+            RegularBackgroundSize::Background64x32,
+            TileFormat::FourBpp,
+        ),
+        Box::new(move |pos: Vector2D<i32>| {
+            let level = &map_tiles::LEVELS[level];
+            let tile = level.foreground_tile(pos.x, pos.y).unwrap_or(0);
+
+            (
+                &level.foreground_tile_set,
+                map_tile::tile_setting(level.foreground_tile_settings, tile),
+            )
+        }),
+    );
+    (background, foreground)
+}
 
-agb::include_background_gfx!(
-    games, "2ce8f4",
-    level_1_background  => 16 deduplicate "gfx/tileSets/level_1/level_1_background.png",
-    level_1_foreground => 16  deduplicate "gfx/tileSets/level_1/level_1_foreground.png"
-);
 const FONT_14: Font = include_font!("font/pixelated.ttf", 14);
 
 pub fn main(mut agb: agb::Gba) -> ! {
+    agb.save.init_sram();
+    let mut best_runs = BestRuns::load(&mut agb.save, map_tiles::LEVELS.len());
+
     let (tiled, mut vram) = agb.display.video.tiled0();
-    vram.set_background_palettes(games::PALETTES);
+    let mut transition = Transition::new(agb.display.blend.get());
+    let mut last_transition = TransitionStyle::Black;
+    vram.set_background_palettes(map_tiles::LEVELS[0].palettes);
     let mut _splash_screen = tiled.background(
         Priority::P0,
         RegularBackgroundSize::Background32x32,
@@ -87,65 +146,47 @@ pub fn main(mut agb: agb::Gba) -> ! {
 
             vblank.wait_for_vblank();
 
-            let current_level_text = format!("level {}", current_level + 1);
-            display_text::write(&mut world_display, current_level_text, &mut vram);
-
-            world_display.commit(&mut vram);
-            world_display.show();
-
-            vblank.wait_for_vblank();
+            // The screen is faded out here so the colours can change without being seen
+            let opening_level = &map_tiles::LEVELS[current_level as usize];
+            vram.set_background_palettes(opening_level.palettes);
+            vram.set_background_palette_raw(&[opening_level.background_colour]);
 
-            let map_current_level = current_level;
-
-            vram.set_background_palettes(games::PALETTES);
-            let mut background = InfiniteScrolledMap::new(
-                tiled.background(
-                    Priority::P0,
-                    RegularBackgroundSize::Background32x64,
-                    TileFormat::FourBpp,
-                ),
-                Box::new(|pos: Vector2D<i32>| {
-                    let level = &map_tiles::LEVELS[map_current_level as usize];
-                    let index = tile_index_math(pos.y, pos.x, level.dimensions.x as i32);
-
-                    (
-                        &level.background_tile_set,
-                        level.background_tile_settings
-                            [*level.background.get(index).unwrap_or(&0) as usize],
-                    )
-                }),
-            );
-            let mut foreground = InfiniteScrolledMap::new(
-                tiled.background(
-                    Priority::P2,
-                    RegularBackgroundSize::Background64x32,
-                    TileFormat::FourBpp,
-                ),
-                Box::new(|pos: Vector2D<i32>| {
-                    let level = &map_tiles::LEVELS[map_current_level as usize];
-                    let index = tile_index_math(pos.y, pos.x, level.dimensions.x as i32);
-                    let tile_file_index = *level.foreground.get(index).unwrap_or(&0) as usize;
-
-                    (
-                        &level.foreground_tile_set,
-                        level.foreground_tile_settings[tile_file_index],
-                    )
-                }),
-            );
+            let (mut background, mut foreground) = level_layers(&tiled, current_level as usize);
 
             let mut level = PlayingLevel::open_level(
                 &map_tiles::LEVELS[current_level as usize],
                 &object,
                 &mut background,
                 &mut foreground,
-                agb::input::ButtonController::new(),
+                Box::new(LiveInput::new()),
             );
 
-            while level.background.init_background(&mut vram) != PartialUpdateStatus::Done {
+            let mut loading_screen = LoadingScreen::new(
+                &mut world_display,
+                &mut vram,
+                opening_level,
+                level.background.loading_steps(),
+            );
+
+            world_display.show();
+
+            transition.fade_in(&vblank, last_transition, &[world_display.background()]);
+
+            loop {
+                let status = level.background.init_background(&mut vram);
+                loading_screen.step(&mut world_display, &mut vram);
+                if status == PartialUpdateStatus::Done {
+                    break;
+                }
                 vblank.wait_for_vblank();
             }
 
-            while level.background.init_foreground(&mut vram) != PartialUpdateStatus::Done {
+            loop {
+                let status = level.background.init_foreground(&mut vram);
+                loading_screen.step(&mut world_display, &mut vram);
+                if status == PartialUpdateStatus::Done {
+                    break;
+                }
                 vblank.wait_for_vblank();
             }
 
@@ -153,14 +194,47 @@ pub fn main(mut agb: agb::Gba) -> ! {
                 vblank.wait_for_vblank();
             }
 
-            object.commit();
+            // Holding select while the level loads races the level's best run
+            let time_attack = ButtonController::new().is_pressed(Button::SELECT);
+            let level_index = current_level as usize;
+            let best_run = best_runs.get(level_index).filter(|_| time_attack);
+            if let Some(best_run) = best_run {
+                level.race_ghost(&object, &best_run.recording);
+            }
+
+            transition.fade_out(
+                &vblank,
+                TransitionStyle::Black,
+                &[world_display.background()],
+            );
+            loading_screen.clear(&mut world_display, &mut vram);
+
+            level.commit_objects(&object);
 
             level.show_backgrounds();
 
             world_display.hide();
 
+            transition.fade_in(&vblank, TransitionStyle::Black, &level.background_ids());
+
+            let mut finished_run = None;
+            if time_attack {
+                transition.show_translucent_objects(&level.background_ids());
+            }
+            let mut split_timer = SplitTimer::new(
+                &mut world_display,
+                &mut vram,
+                opening_level,
+                time_attack,
+                best_run.map(|best_run| best_run.frames),
+            );
+            world_display.show();
+
             loop {
-                match level.update_frame(&mut vram, &object) {
+                let update_state = level.update_frame(&mut vram);
+                split_timer.update(&mut world_display, &mut vram, level.timer as u32);
+
+                match update_state {
                     UpdateState::Normal => {}
                     UpdateState::Dead => {
                         // display_text::write(
@@ -173,27 +247,51 @@ pub fn main(mut agb: agb::Gba) -> ! {
 
                         for i in 0..=8 {
                             level.dead_update(&object, i);
-                            object.commit();
+                            level.commit_objects(&object);
                             if i != 5 {
                                 delay(&vblank, 7);
                             }
                         }
 
+                        last_transition = TransitionStyle::Mosaic;
                         break;
                     }
                     UpdateState::Complete => {
+                        split_timer.finish(&mut world_display, &mut vram, level.timer as u32);
+                        delay(&vblank, RESULTS_WAIT);
+                        if time_attack {
+                            finished_run = Some(BestRun::new(level.recording().clone()));
+                        }
+
                         current_level += 1;
+                        last_transition = TransitionStyle::White;
                         break;
                     }
                 }
 
                 // sfx.frame();
                 vblank.wait_for_vblank();
-                object.commit();
+                level.commit_objects(&object);
             }
 
+            split_timer.clear(&mut world_display, &mut vram);
+            world_display.hide();
+
+            transition.fade_out(&vblank, last_transition, &level.background_ids());
+
             level.hide_backgrounds();
             level.clear_backgrounds(&mut vram);
+            drop(level);
+
+            if let Some(run) = finished_run {
+                if best_runs
+                    .get(level_index)
+                    .map_or(true, |best_run| best_run.is_beaten_by(run.frames))
+                {
+                    // Still raced until the GBA is turned off if it couldn't be saved
+                    let _ = best_runs.set(&mut agb.save, level_index, run);
+                }
+            }
         }
 
         object.commit();
@@ -206,10 +304,6 @@ pub fn main(mut agb: agb::Gba) -> ! {
         // );
     }
 
-    fn tile_index_math(y: i32, x: i32, width: i32) -> usize {
-        (y * width as i32 + x) as usize
-    }
-
     fn delay(vblank: &agb::interrupt::VBlank, frames: u32) {
         for _ in 0..frames {
             vblank.wait_for_vblank();
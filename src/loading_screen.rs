@@ -0,0 +1,87 @@
+use crate::level::Level;
+use crate::FONT_14;
+use agb::display::font::TextRenderer;
+use agb::display::tiled::{DynamicTile, RegularMap, TiledMap, VRamManager};
+use agb::fixnum::Vector2D;
+use core::fmt::Write;
+
+const FRAMES_PER_SECOND: i32 = 60;
+const TEXT_COLOUR: u8 = 8;
+const TEXT_POSITION: Vector2D<u16> = Vector2D::new(2, 5);
+/// Left of the progress bar, in tiles
+const BAR_POSITION: Vector2D<u16> = Vector2D::new(5, 14);
+const BAR_WIDTH: u16 = 20;
+
+/// Shows the level name, subtitle, intro text and par time with a progress bar while the
+/// level's maps are filled in
+pub struct LoadingScreen {
+    text_renderer: TextRenderer<'static>,
+    bar_tile: DynamicTile<'static>,
+    bar_filled: u16,
+    steps: i32,
+    total_steps: i32,
+}
+
+impl LoadingScreen {
+    pub fn new(
+        map: &mut RegularMap,
+        vram: &mut VRamManager,
+        level: &Level,
+        total_steps: i32,
+    ) -> Self {
+        map.set_scroll_pos((0i16, 0i16).into());
+
+        let mut text_renderer = FONT_14.render_text(TEXT_POSITION);
+        let mut text_writer = text_renderer.writer(TEXT_COLOUR, 0, map, vram);
+
+        writeln!(&mut text_writer, "{}", level.name).unwrap();
+        if let Some(subtitle) = level.subtitle {
+            writeln!(&mut text_writer, "{subtitle}").unwrap();
+        }
+        if let Some(intro) = level.intro {
+            writeln!(&mut text_writer, "{intro}").unwrap();
+        }
+        if let Some(par_time) = level.par_time {
+            let seconds = par_time / FRAMES_PER_SECOND;
+            writeln!(&mut text_writer, "Par {}:{:02}", seconds / 60, seconds % 60).unwrap();
+        }
+        text_writer.commit();
+
+        map.commit(vram);
+
+        LoadingScreen {
+            text_renderer,
+            bar_tile: vram.new_dynamic_tile().fill_with(TEXT_COLOUR),
+            bar_filled: 0,
+            steps: 0,
+            total_steps,
+        }
+    }
+
+    /// Call once for each partial update of the level, including the last, fills the bar in as
+    /// loading goes on
+    pub fn step(&mut self, map: &mut RegularMap, vram: &mut VRamManager) {
+        self.steps += 1;
+        let filled =
+            (BAR_WIDTH as i32 * self.steps / self.total_steps).min(BAR_WIDTH as i32) as u16;
+
+        for x in self.bar_filled..filled {
+            map.set_tile(
+                vram,
+                (BAR_POSITION.x + x, BAR_POSITION.y).into(),
+                &self.bar_tile.tile_set(),
+                self.bar_tile.tile_setting(),
+            );
+        }
+        self.bar_filled = filled;
+
+        map.commit(vram);
+    }
+
+    pub fn clear(mut self, map: &mut RegularMap, vram: &mut VRamManager) {
+        self.text_renderer.clear(vram);
+        map.clear(vram);
+        map.commit(vram);
+        vram.remove_dynamic_tile(self.bar_tile);
+    }
+}
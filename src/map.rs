@@ -1,24 +1,43 @@
 use crate::level::Level;
-use crate::types::FixedNumberType;
 use agb::display::tiled::{InfiniteScrolledMap, PartialUpdateStatus, VRamManager};
+use agb::display::HEIGHT;
 use agb::fixnum::Vector2D;
+use simulation::types::FixedNumberType;
 
 pub struct Map<'a, 'b> {
     pub background: &'a mut InfiniteScrolledMap<'b>,
     pub foreground: &'a mut InfiniteScrolledMap<'b>,
     pub position: Vector2D<FixedNumberType>,
+    /// Screen shake offset added on top of `position` when drawing
+    pub shake: Vector2D<i32>,
     pub level: &'a Level<'a>,
 }
 
 impl<'a, 'b> Map<'a, 'b> {
+    /// Where the screen is drawn from, including any screen shake
+    pub fn screen_position(&self) -> Vector2D<FixedNumberType> {
+        self.position + self.shake.change_base()
+    }
+
     pub fn commit_position(&mut self, vram: &mut VRamManager) {
-        self.background.set_pos(vram, self.position.floor());
-        self.foreground.set_pos(vram, self.position.floor());
+        self.background
+            .set_pos(vram, self.screen_position().floor());
+        self.foreground
+            .set_pos(vram, self.screen_position().floor());
 
         self.background.commit(vram);
         self.foreground.commit(vram);
     }
 
+    /// How many calls of `init_background` and `init_foreground` together it takes to fill
+    /// both layers in. `InfiniteScrolledMap::init_partial` copies two rows of tiles a call,
+    /// from the row the screen starts in to one past the row it ends in
+    pub fn loading_steps(&self) -> i32 {
+        let y = self.position.floor().y;
+        let rows = (y + HEIGHT + 7).div_euclid(8) + 1 - y.div_euclid(8);
+        2 * ((rows + 1) / 2)
+    }
+
     pub fn init_background(&mut self, vram: &mut VRamManager) -> PartialUpdateStatus {
         self.background.init_partial(vram, self.position.floor())
     }
@@ -1,21 +1,53 @@
-use crate::entities::enemies;
 use crate::level::Level;
 use crate::map::Map;
-use crate::player::{Player, WARRIOR_DEAD_END_ANIMATION, WARRIOR_DEAD_START_ANIMATION};
-use crate::types::{FixedNumberType, TILE_SIZE};
-use agb::display::object::{Graphics, OamManaged, Tag};
-use agb::display::tiled::{InfiniteScrolledMap, VRamManager};
-use agb::display::{Priority, HEIGHT, WIDTH};
+use crate::sprites::{
+    set_semi_transparent, ObjectSprite, WARRIOR_DEAD_END_ANIMATION, WARRIOR_DEAD_START_ANIMATION,
+};
+use agb::display::object::OamManaged;
+use agb::display::tiled::{BackgroundID, InfiniteScrolledMap, VRamManager};
+use agb::display::Priority;
 use agb::fixnum::Vector2D;
-use agb::input::ButtonController;
+use alloc::boxed::Box;
+use simulation::camera::Camera;
+use simulation::enemies;
+use simulation::input::{Buttons, InputSource};
+use simulation::player::Player;
+use simulation::replay::{Recorder, Recording, Replay};
+use simulation::sprite::Sprite;
+use simulation::types::FixedNumberType;
 use UpdateState::{Complete, Dead, Normal};
 
-pub struct PlayingLevel<'a, 'b> {
+/// Screen shake `(frames, pixels)` and hit-stop frames when the player kills an enemy
+const ENEMY_KILLED_SHAKE: (i32, i32) = (8, 2);
+const ENEMY_KILLED_HIT_STOP: i32 = 4;
+/// Screen shake `(frames, pixels)` and hit-stop frames when the player is hit
+const PLAYER_HIT_SHAKE: (i32, i32) = (16, 4);
+const PLAYER_HIT_HIT_STOP: i32 = 12;
+/// The ghost is drawn in front of every other object, so it's committed to the first OAM slot
+const GHOST_OAM_SLOT: usize = 0;
+
+/// A best run of the level played back next to the player
+struct Ghost<'a, 'gba> {
+    player: Player<ObjectSprite<'a, 'gba>>,
+    input: Replay<'a>,
+    buttons: Buttons,
+}
+
+pub struct PlayingLevel<'a, 'b, 'gba> {
     pub timer: i32,
     pub background: Map<'a, 'b>,
-    pub input: ButtonController,
-    pub player: Player<'a>,
-    enemies: [enemies::Enemy<'a>; 16],
+    /// Everything played is recorded so it can be replayed
+    input: Recorder<Box<dyn InputSource + 'a>>,
+    /// The buttons from `input` the simulation is given each frame
+    pub buttons: Buttons,
+    pub player: Player<ObjectSprite<'a, 'gba>>,
+    pub camera: Camera,
+    /// Frames left where the game is frozen to make a hit land harder
+    pub hit_stop: i32,
+    /// The player has been hit and dies once the hit-stop is over
+    player_hit: bool,
+    enemies: [enemies::Enemy<ObjectSprite<'a, 'gba>>; 16],
+    ghost: Option<Ghost<'a, 'gba>>,
 }
 
 pub enum UpdateState {
@@ -24,34 +56,21 @@ pub enum UpdateState {
     Complete,
 }
 
-impl<'a, 'b> PlayingLevel<'a, 'b> {
+impl<'a, 'b, 'gba> PlayingLevel<'a, 'b, 'gba> {
     pub fn open_level(
         level: &'a Level,
-        object_control: &'a OamManaged,
+        object_control: &'a OamManaged<'gba>,
         background: &'a mut InfiniteScrolledMap<'b>,
         foreground: &'a mut InfiniteScrolledMap<'b>,
-        input: ButtonController,
+        input: Box<dyn InputSource + 'a>,
     ) -> Self {
-        let mut e: [enemies::Enemy<'a>; 16] = Default::default();
-        let mut enemy_count = 0;
-        for &boar in level.boars {
-            e[enemy_count] = enemies::Enemy::new_boar(object_control, boar.into());
-            enemy_count += 1;
+        let mut e: [enemies::Enemy<ObjectSprite<'a, 'gba>>; 16] = Default::default();
+        for (enemy, &boar) in e.iter_mut().zip(level.world.boars) {
+            *enemy = enemies::Enemy::new_boar(ObjectSprite::new(object_control), boar.into());
         }
 
-        let start_pos: Vector2D<FixedNumberType> = level.start_pos.into();
-
-        let background_position = (
-            (start_pos.x - WIDTH / 2).clamp(
-                0.into(),
-                ((level.dimensions.x * TILE_SIZE) as i32 - WIDTH).into(),
-            ),
-            (start_pos.y - HEIGHT / 2).clamp(
-                0.into(),
-                ((level.dimensions.y * TILE_SIZE) as i32 - HEIGHT).into(),
-            ),
-        )
-            .into();
+        let start_pos: Vector2D<FixedNumberType> = level.world.start_pos.into();
+        let camera = Camera::new(&level.world, start_pos);
 
         PlayingLevel {
             timer: 0,
@@ -59,14 +78,59 @@ impl<'a, 'b> PlayingLevel<'a, 'b> {
                 background,
                 foreground,
                 level,
-                position: background_position,
+                position: camera.position,
+                shake: (0, 0).into(),
             },
-            player: Player::new(object_control, start_pos),
-            input,
+            player: Player::new(ObjectSprite::new(object_control), start_pos),
+            camera,
+            hit_stop: 0,
+            player_hit: false,
+            input: Recorder::new(input),
+            buttons: Buttons::default(),
             enemies: e,
+            ghost: None,
+        }
+    }
+
+    /// Races the player against a replay of `best`, shown as a see-through warrior. It only
+    /// runs on the frames the player does so both are timed the same way
+    pub fn race_ghost(&mut self, object_control: &'a OamManaged<'gba>, best: &'a Recording) {
+        let mut sprite = ObjectSprite::new(object_control);
+        sprite.object.set_z(i32::MIN);
+
+        let start_pos = self.background.level.world.start_pos.into();
+        self.ghost = Some(Ghost {
+            player: Player::new(sprite, start_pos),
+            input: best.replay(),
+            buttons: Buttons::default(),
+        });
+    }
+
+    /// Commits every object to OAM, then makes the ghost see-through
+    pub fn commit_objects(&self, object_control: &OamManaged) {
+        object_control.commit();
+
+        if let Some(ghost) = &self.ghost {
+            let sprite = &ghost.player.warrior.sprite;
+            if sprite.object.is_visible() {
+                set_semi_transparent(GHOST_OAM_SLOT, sprite);
+            }
         }
     }
 
+    /// The buttons held every frame the level has been played for, which plays it the same
+    /// way again when given to a newly opened level
+    pub fn recording(&self) -> &Recording {
+        self.input.recording()
+    }
+
+    pub fn background_ids(&self) -> [BackgroundID; 2] {
+        [
+            self.background.background.background(),
+            self.background.foreground.background(),
+        ]
+    }
+
     pub fn show_backgrounds(&mut self) {
         self.background.background.show();
         self.background.foreground.show();
@@ -84,7 +148,10 @@ impl<'a, 'b> PlayingLevel<'a, 'b> {
 
     pub fn dead_start(&mut self) {
         self.player.warrior.velocity = (0, -1).into();
-        self.player.warrior.sprite.set_priority(Priority::P0);
+        self.player.warrior.sprite.object.set_priority(Priority::P0);
+        if let Some(ghost) = &mut self.ghost {
+            ghost.player.warrior.sprite.hide();
+        }
     }
 
     /// Controls mostly the dead animation while *dying*
@@ -95,6 +162,7 @@ impl<'a, 'b> PlayingLevel<'a, 'b> {
             self.player
                 .warrior
                 .sprite
+                .object
                 .set_sprite(controller.sprite(start_frame));
             self.player
                 .warrior
@@ -104,6 +172,7 @@ impl<'a, 'b> PlayingLevel<'a, 'b> {
             self.player
                 .warrior
                 .sprite
+                .object
                 .set_sprite(controller.sprite(frame));
             self.player
                 .warrior
@@ -111,54 +180,67 @@ impl<'a, 'b> PlayingLevel<'a, 'b> {
         }
     }
 
-    pub fn update_frame(
-        &mut self,
-        vram: &mut VRamManager,
-        controller: &'a OamManaged,
-    ) -> UpdateState {
+    pub fn update_frame(&mut self, vram: &mut VRamManager) -> UpdateState {
+        if self.hit_stop > 0 {
+            self.hit_stop -= 1;
+            self.commit_positions(vram);
+
+            return if self.hit_stop == 0 && self.player_hit {
+                Dead
+            } else {
+                Normal
+            };
+        }
+
         self.timer += 1;
-        self.input.update();
+        self.buttons.update(self.input.next_frame());
 
+        let world = &self.background.level.world;
         let mut player_dead = false;
-        self.player
-            .update_frame(&self.input, controller, self.timer, self.background.level);
+        self.player.update_frame(&self.buttons, self.timer, world);
+        if let Some(ghost) = &mut self.ghost {
+            ghost.buttons.update(ghost.input.next_frame());
+            ghost.player.update_frame(&ghost.buttons, self.timer, world);
+        }
 
         for enemy in self.enemies.iter_mut() {
             match enemy.update(
-                controller,
-                self.background.level,
+                world,
                 self.player.warrior.position,
                 &self.player.action,
                 self.timer,
             ) {
                 enemies::EnemyUpdateState::KillPlayer => player_dead = true,
+                enemies::EnemyUpdateState::Killed => {
+                    self.camera
+                        .shake(ENEMY_KILLED_SHAKE.0, ENEMY_KILLED_SHAKE.1);
+                    self.hit_stop = self.hit_stop.max(ENEMY_KILLED_HIT_STOP);
+                }
                 enemies::EnemyUpdateState::None => {}
             }
         }
 
-        self.background.position = self.get_next_map_position();
-        self.background.commit_position(vram);
-
-        self.player
-            .warrior
-            .commit_position(self.background.position - self.player.sprite_off_set);
-
-        // self.player.hat.commit_position(self.background.position);
-
-        for enemy in self.enemies.iter_mut() {
-            enemy.commit(self.background.position);
-        }
+        self.background.position = self.camera.update(
+            world,
+            self.player.warrior.position,
+            self.player.facing,
+            self.player.is_on_ground,
+        );
+        self.commit_positions(vram);
 
         player_dead |= self
             .player
             .warrior
-            .killision_at_point(self.background.level, self.player.warrior.position);
+            .killision_at_point(world, self.player.warrior.position);
         if player_dead {
-            Dead
+            self.player_hit = true;
+            self.camera.shake(PLAYER_HIT_SHAKE.0, PLAYER_HIT_SHAKE.1);
+            self.hit_stop = PLAYER_HIT_HIT_STOP;
+            Normal
         } else if self
             .player
             .warrior
-            .completion_at_point(self.background.level, self.player.warrior.position)
+            .completion_at_point(world, self.player.warrior.position)
         {
             Complete
         } else {
@@ -166,28 +248,303 @@ impl<'a, 'b> PlayingLevel<'a, 'b> {
         }
     }
 
-    fn get_next_map_position(&self) -> Vector2D<FixedNumberType> {
-        // want to ensure the player and the hat are visible if possible, so try to position the map
-        // so the centre is at the average position. But give the player some extra priority
+    /// Moves the backgrounds and sprites to where the camera and any screen shake put them
+    fn commit_positions(&mut self, vram: &mut VRamManager) {
+        self.background.shake = self.camera.shake_offset();
+        self.background.commit_position(vram);
 
-        let player_pos = self.player.warrior.position.floor();
-        let new_target_position = (player_pos * 3) / 4;
+        let screen_position = self.background.screen_position();
+        self.player
+            .warrior
+            .commit_position(screen_position - self.player.sprite_off_set);
 
-        let screen: Vector2D<i32> = (WIDTH, HEIGHT).into();
-        let half_screen = screen / 2;
-        let current_centre = self.background.position.floor() + half_screen;
+        // self.player.hat.commit_position(self.background.position);
 
-        let mut target_position = ((current_centre * 3 + new_target_position) / 4) - half_screen;
+        if let Some(ghost) = &mut self.ghost {
+            // The best run is over once its ghost has finished the level
+            if ghost.input.is_finished() {
+                ghost.player.warrior.sprite.hide();
+            } else {
+                ghost
+                    .player
+                    .warrior
+                    .commit_position(screen_position - ghost.player.sprite_off_set);
+            }
+        }
 
-        target_position.x = target_position.x.clamp(
-            0,
-            (self.background.level.dimensions.x * TILE_SIZE - (WIDTH as u32)) as i32,
-        );
-        target_position.y = target_position.y.clamp(
-            0,
-            (self.background.level.dimensions.y * TILE_SIZE - (HEIGHT as u32)) as i32,
+        for enemy in self.enemies.iter_mut() {
+            enemy.commit(screen_position);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PlayingLevel, UpdateState};
+    use crate::level::map_tiles::LEVELS;
+    use crate::level_layers;
+    use agb::display::tiled::{PartialUpdateStatus, VRamManager};
+    use agb::fixnum::Vector2D;
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+    use simulation::enemies::Enemy;
+    use simulation::input::{button, InputSource};
+    use simulation::player::PlayerAction;
+    use simulation::replay::Recording;
+    use simulation::types::FixedNumberType;
+
+    /// Opens `LEVELS[level]` the same way the game does, played with `input`, and gives it to
+    /// `test`
+    fn with_level(
+        gba: &mut agb::Gba,
+        level: usize,
+        input: impl InputSource,
+        test: impl FnOnce(&mut PlayingLevel, &mut VRamManager),
+    ) {
+        with_level_racing(gba, level, input, None, test);
+    }
+
+    /// [with_level], with a ghost replaying `best` if there is one
+    fn with_level_racing(
+        gba: &mut agb::Gba,
+        level: usize,
+        input: impl InputSource,
+        best: Option<&Recording>,
+        test: impl FnOnce(&mut PlayingLevel, &mut VRamManager),
+    ) {
+        let (tiled, mut vram) = gba.display.video.tiled0();
+        let object = gba.display.object.get_managed();
+        let (mut background, mut foreground) = level_layers(&tiled, level);
+        vram.set_background_palettes(LEVELS[level].palettes);
+
+        let mut playing_level = PlayingLevel::open_level(
+            &LEVELS[level],
+            &object,
+            &mut background,
+            &mut foreground,
+            Box::new(input),
         );
+        if let Some(best) = best {
+            playing_level.race_ghost(&object, best);
+        }
+        while playing_level.background.init_background(&mut vram) != PartialUpdateStatus::Done {}
+        while playing_level.background.init_foreground(&mut vram) != PartialUpdateStatus::Done {}
+
+        test(&mut playing_level, &mut vram);
+
+        playing_level.clear_backgrounds(&mut vram);
+    }
+
+    /// Nothing is pressed
+    const NO_INPUT: Recording = Recording::new();
+
+    /// Runs up to `frames` frames, stopping at the first one `done` is true for. Returns whether
+    /// it ever was
+    fn run_until(
+        playing_level: &mut PlayingLevel,
+        vram: &mut VRamManager,
+        frames: i32,
+        mut done: impl FnMut(&UpdateState, &PlayingLevel) -> bool,
+    ) -> bool {
+        (0..frames).any(|_| {
+            let state = playing_level.update_frame(vram);
+            done(&state, playing_level)
+        })
+    }
+
+    /// The first level's boar, which starts far enough away to leave the player alone
+    fn boar_position(playing_level: &PlayingLevel) -> Vector2D<FixedNumberType> {
+        playing_level.background.level.world.boars[0].into()
+    }
+
+    #[test_case]
+    fn every_level_opens_and_plays(gba: &mut agb::Gba) {
+        for (index, level) in LEVELS.iter().enumerate() {
+            with_level(gba, index, NO_INPUT.replay(), |playing_level, vram| {
+                assert!(
+                    !run_until(playing_level, vram, 60, |state, _| !matches!(
+                        state,
+                        UpdateState::Normal
+                    )),
+                    "{} ended without any input",
+                    level.name
+                );
+            });
+        }
+    }
+
+    #[test_case]
+    fn loading_steps_match_the_updates_it_takes_to_load(gba: &mut agb::Gba) {
+        for index in 0..LEVELS.len() {
+            with_level(gba, index, NO_INPUT.replay(), |playing_level, vram| {
+                let map = &mut playing_level.background;
+                // Both lined up with the tiles and part way into one, which covers another row
+                for offset in [0, 3] {
+                    let y = map.position.y.floor().div_euclid(8) * 8 + offset;
+                    map.position.y = FixedNumberType::new(y);
+
+                    let mut steps = 0;
+                    loop {
+                        steps += 1;
+                        if map.init_background(vram) == PartialUpdateStatus::Done {
+                            break;
+                        }
+                    }
+                    loop {
+                        steps += 1;
+                        if map.init_foreground(vram) == PartialUpdateStatus::Done {
+                            break;
+                        }
+                    }
+                    assert_eq!(steps, map.loading_steps());
+                }
+            });
+        }
+    }
+
+    #[test_case]
+    fn no_level_spawns_the_player_inside_collision(gba: &mut agb::Gba) {
+        for (index, level) in LEVELS.iter().enumerate() {
+            with_level(gba, index, NO_INPUT.replay(), |playing_level, _| {
+                let warrior = &playing_level.player.warrior;
+                assert!(
+                    !warrior.collision_at_point(&level.world, warrior.position),
+                    "{} starts the player in a wall",
+                    level.name
+                );
+            });
+        }
+    }
+
+    #[test_case]
+    fn the_player_falls_onto_the_ground(gba: &mut agb::Gba) {
+        with_level(gba, 0, NO_INPUT.replay(), |playing_level, vram| {
+            let world = &LEVELS[0].world;
+            let raised = playing_level.player.warrior.position - (0, 16).into();
+            assert!(!playing_level
+                .player
+                .warrior
+                .collision_at_point(world, raised));
+            playing_level.player.warrior.position = raised;
+
+            run_until(playing_level, vram, 120, |_, _| false);
+
+            let warrior = &playing_level.player.warrior;
+            assert!(playing_level.player.is_on_ground);
+            assert!(!warrior.collision_at_point(world, warrior.position));
+            assert!(warrior.collision_at_point(world, warrior.position + (0, 1).into()));
+        });
+    }
+
+    #[test_case]
+    fn a_boar_kills_an_idle_player(gba: &mut agb::Gba) {
+        with_level(gba, 0, NO_INPUT.replay(), |playing_level, vram| {
+            playing_level.player.warrior.position.x = boar_position(playing_level).x - 10;
+
+            assert!(run_until(playing_level, vram, 60, |state, _| matches!(
+                state,
+                UpdateState::Dead
+            )));
+        });
+    }
+
+    #[test_case]
+    fn attacking_a_boar_kills_it(gba: &mut agb::Gba) {
+        with_level(gba, 0, NO_INPUT.replay(), |playing_level, vram| {
+            playing_level.player.warrior.position.x = boar_position(playing_level).x - 10;
+            playing_level.player.action = PlayerAction::Attack;
+
+            let mut died = false;
+            let boar_gone = run_until(playing_level, vram, 120, |state, playing_level| {
+                died |= matches!(state, UpdateState::Dead);
+                matches!(playing_level.enemies[0], Enemy::Empty)
+            });
+
+            assert!(boar_gone && !died);
+        });
+    }
+
+    #[test_case]
+    fn reaching_a_win_tile_completes_the_level(gba: &mut agb::Gba) {
+        with_level(gba, 0, NO_INPUT.replay(), |playing_level, vram| {
+            let world = &LEVELS[0].world;
+            let (width, height) = (world.dimensions.x as i32, world.dimensions.y as i32);
+            let (x, y) = (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .find(|&(x, y)| world.wins(x, y, u64::MAX))
+                .expect("the first level should have a win tile");
+            playing_level.player.warrior.position = (x * 8 + 4, y * 8 + 4).into();
+
+            assert!(run_until(playing_level, vram, 10, |state, _| matches!(
+                state,
+                UpdateState::Complete
+            )));
+        });
+    }
+
+    #[test_case]
+    fn replaying_a_recording_plays_the_same_way(gba: &mut agb::Gba) {
+        // Runs at the boar, jumps, and swings at it
+        let script = Recording::from_runs(alloc::vec![
+            (0, 10),
+            (button::RIGHT, 20),
+            (button::RIGHT | button::A, 1),
+            (button::RIGHT, 25),
+            (button::B, 1),
+            (0, 60),
+        ]);
+
+        let play = |gba: &mut agb::Gba, input| {
+            let mut recording = Recording::new();
+            let mut positions = Vec::new();
+            with_level(gba, 0, input, |playing_level, vram| {
+                run_until(
+                    playing_level,
+                    vram,
+                    script.frames() as i32,
+                    |_, playing_level| {
+                        positions.push(playing_level.player.warrior.position);
+                        false
+                    },
+                );
+                recording = playing_level.recording().clone();
+            });
+            (recording, positions)
+        };
+
+        let (recording, played) = play(gba, script.replay());
+        assert_eq!(recording, script);
+
+        let (_, replayed) = play(gba, recording.replay());
+        assert_eq!(played, replayed);
+    }
+
+    #[test_case]
+    fn the_ghost_follows_the_best_run(gba: &mut agb::Gba) {
+        // Walks away from the boar and jumps
+        let best = Recording::from_runs(alloc::vec![
+            (button::LEFT, 10),
+            (button::LEFT | button::A, 1),
+            (0, 40),
+        ]);
+
+        with_level_racing(gba, 0, best.replay(), Some(&best), |playing_level, vram| {
+            run_until(
+                playing_level,
+                vram,
+                best.frames() as i32,
+                |_, playing_level| {
+                    let ghost = &playing_level.ghost.as_ref().unwrap().player;
+                    assert_eq!(
+                        ghost.warrior.position,
+                        playing_level.player.warrior.position
+                    );
+                    false
+                },
+            );
 
-        target_position.into()
+            let ghost = &playing_level.ghost.as_ref().unwrap().player;
+            assert!(!ghost.warrior.sprite.object.is_visible());
+        });
     }
 }
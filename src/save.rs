@@ -0,0 +1,69 @@
+use agb::save::{Error, SaveManager};
+use alloc::vec;
+use alloc::vec::Vec;
+use simulation::time_attack::BestRun;
+
+/// At the start of the save so a blank save isn't read as best runs. The last byte is the
+/// version of the layout after it
+const MAGIC: [u8; 4] = *b"UGB\x01";
+/// Bytes kept for each level's best run, one after the other after `MAGIC`. SRAM is 32KiB so
+/// there's room for 7 levels
+const SLOT_SIZE: usize = 4096;
+
+fn slot_offset(level: usize) -> usize {
+    MAGIC.len() + level * SLOT_SIZE
+}
+
+/// The best run of every level, kept in battery backed SRAM
+pub struct BestRuns {
+    runs: Vec<Option<BestRun>>,
+}
+
+impl BestRuns {
+    /// Reads the best runs of the first `levels` levels. There are none if nothing has been
+    /// saved yet or the save can't be read
+    pub fn load(save: &mut SaveManager, levels: usize) -> Self {
+        BestRuns {
+            runs: read_runs(save, levels).unwrap_or_else(|_| vec![None; levels]),
+        }
+    }
+
+    pub fn get(&self, level: usize) -> Option<&BestRun> {
+        self.runs.get(level)?.as_ref()
+    }
+
+    /// Makes `run` the best run of `level` and saves every level's best run. It stays the best
+    /// run until the GBA is turned off even if saving fails
+    pub fn set(&mut self, save: &mut SaveManager, level: usize, run: BestRun) -> Result<(), Error> {
+        self.runs[level] = Some(run);
+
+        let mut save_data = save.access()?;
+        let mut block = save_data.prepare_write(0..slot_offset(self.runs.len()))?;
+        block.write(0, &MAGIC)?;
+        for (level, run) in self.runs.iter().enumerate() {
+            match run {
+                Some(run) => block.write(slot_offset(level), &run.to_bytes(SLOT_SIZE))?,
+                None => block.write(slot_offset(level), &BestRun::none_bytes())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_runs(save: &mut SaveManager, levels: usize) -> Result<Vec<Option<BestRun>>, Error> {
+    let mut save_data = save.access()?;
+
+    let mut magic = [0; MAGIC.len()];
+    save_data.read(0, &mut magic)?;
+    if magic != MAGIC {
+        return Ok(vec![None; levels]);
+    }
+
+    let mut slot = vec![0; SLOT_SIZE];
+    (0..levels)
+        .map(|level| {
+            save_data.read(slot_offset(level), &mut slot)?;
+            Ok(BestRun::from_bytes(&slot))
+        })
+        .collect()
+}
@@ -0,0 +1,177 @@
+use crate::level::Level;
+use crate::FONT_14;
+use agb::display::font::TextRenderer;
+use agb::display::tiled::{DynamicTile, RegularMap, TiledMap, VRamManager};
+use agb::fixnum::Vector2D;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use simulation::time_attack::{Split, Time};
+
+const TEXT_COLOUR: u8 = 8;
+const TEXT_POSITION: Vector2D<u16> = Vector2D::new(1, 0);
+/// The time played goes under the name a character to a tile, every character of the font's
+/// digits and punctuation fits in one tile across and two down
+const TIME_POSITION: Vector2D<u16> = Vector2D::new(1, 2);
+const CELL_HEIGHT: u16 = 2;
+/// The par or best time goes after room for a time of `mm:ss.hh`
+const TIME_TO_BEAT_POSITION: Vector2D<u16> = Vector2D::new(TIME_POSITION.x + 9, 2);
+/// The time is redrawn every tenth of a second rather than every frame
+const FRAMES_PER_REDRAW: u32 = 6;
+
+/// The HUD, shows the level's name and how long it has been played for next to its par time,
+/// or the time to beat in time attack. At the end it shows the results, how far ahead or
+/// behind of those the level was finished
+pub struct SplitTimer {
+    /// The name, and the results at the end
+    text_renderer: TextRenderer<'static>,
+    time_to_beat: TextRenderer<'static>,
+    /// One for each character of the time played so only the ones that change are redrawn
+    time_cells: Vec<TextRenderer<'static>>,
+    /// Put down over a cell before it is redrawn, as the new character might not cover
+    /// every tile the old one did
+    blank_tile: DynamicTile<'static>,
+    /// Time to beat in frames from the level
+    par_time: Option<u32>,
+    /// Frames the best run took, only while racing it in time attack
+    best: Option<u32>,
+    time_attack: bool,
+    shown: Option<u32>,
+    shown_text: String,
+}
+
+impl SplitTimer {
+    pub fn new(
+        map: &mut RegularMap,
+        vram: &mut VRamManager,
+        level: &Level,
+        time_attack: bool,
+        best: Option<u32>,
+    ) -> Self {
+        map.set_scroll_pos((0i16, 0i16).into());
+
+        let par_time = level.par_time.map(|par_time| par_time as u32);
+        let mut text_renderer = FONT_14.render_text(TEXT_POSITION);
+        let mut text_writer = text_renderer.writer(TEXT_COLOUR, 0, map, vram);
+        write!(&mut text_writer, "{}", level.name).unwrap();
+        text_writer.commit();
+
+        let mut time_to_beat = FONT_14.render_text(TIME_TO_BEAT_POSITION);
+        let mut text_writer = time_to_beat.writer(TEXT_COLOUR, 0, map, vram);
+        match (best, par_time) {
+            (Some(best), _) => write!(&mut text_writer, "Best {}", Time(best)).unwrap(),
+            (None, Some(par_time)) => write!(&mut text_writer, "Par {}", Time(par_time)).unwrap(),
+            (None, None) => {}
+        }
+        text_writer.commit();
+
+        let mut split_timer = SplitTimer {
+            text_renderer,
+            time_to_beat,
+            time_cells: Vec::new(),
+            blank_tile: vram.new_dynamic_tile().fill_with(0),
+            par_time,
+            best,
+            time_attack,
+            shown: None,
+            shown_text: String::new(),
+        };
+        split_timer.update(map, vram, 0);
+        split_timer
+    }
+
+    /// Shows `frames` as the time played if it's been long enough since it was last redrawn
+    pub fn update(&mut self, map: &mut RegularMap, vram: &mut VRamManager, frames: u32) {
+        let redraw = frames / FRAMES_PER_REDRAW;
+        if self.shown == Some(redraw) {
+            return;
+        }
+        self.shown = Some(redraw);
+
+        let mut text = String::new();
+        write!(&mut text, "{}", Time(frames)).unwrap();
+
+        for (index, character) in text.chars().enumerate() {
+            if self.shown_text.chars().nth(index) != Some(character) {
+                self.redraw_cell(map, vram, index, character);
+            }
+        }
+        self.shown_text = text;
+
+        map.commit(vram);
+    }
+
+    /// Replaces the HUD with the time the level was finished in and how it compares to the par
+    /// time, and to the best run in time attack
+    pub fn finish(&mut self, map: &mut RegularMap, vram: &mut VRamManager, frames: u32) {
+        self.clear_text(map, vram);
+
+        let mut text_writer = self.text_renderer.writer(TEXT_COLOUR, 0, map, vram);
+        write!(&mut text_writer, "{}", Time(frames)).unwrap();
+        if let Some(par_time) = self.par_time {
+            write!(&mut text_writer, "  Par {}", Split::new(frames, par_time)).unwrap();
+        }
+        if self.time_attack {
+            match self.best {
+                Some(best) if frames < best => write!(
+                    &mut text_writer,
+                    "\nBest {}  New best!",
+                    Split::new(frames, best)
+                ),
+                Some(best) => write!(&mut text_writer, "\nBest {}", Split::new(frames, best)),
+                None => write!(&mut text_writer, "\nNew best!"),
+            }
+            .unwrap();
+        }
+        text_writer.commit();
+
+        map.commit(vram);
+    }
+
+    pub fn clear(mut self, map: &mut RegularMap, vram: &mut VRamManager) {
+        self.clear_text(map, vram);
+        map.commit(vram);
+        vram.remove_dynamic_tile(self.blank_tile);
+    }
+
+    /// The renderers forget their old tiles when cleared, so the map is cleared with them
+    fn clear_text(&mut self, map: &mut RegularMap, vram: &mut VRamManager) {
+        self.text_renderer.clear(vram);
+        self.time_to_beat.clear(vram);
+        for mut cell in self.time_cells.drain(..) {
+            cell.clear(vram);
+        }
+        self.shown_text.clear();
+        map.clear(vram);
+    }
+
+    /// Draws `character` as the `index`th character of the time played
+    fn redraw_cell(
+        &mut self,
+        map: &mut RegularMap,
+        vram: &mut VRamManager,
+        index: usize,
+        character: char,
+    ) {
+        let position = TIME_POSITION + (index as u16, 0).into();
+        while self.time_cells.len() <= index {
+            let next = TIME_POSITION + (self.time_cells.len() as u16, 0).into();
+            self.time_cells.push(FONT_14.render_text(next));
+        }
+
+        for y in 0..CELL_HEIGHT {
+            map.set_tile(
+                vram,
+                position + (0, y).into(),
+                &self.blank_tile.tile_set(),
+                self.blank_tile.tile_setting(),
+            );
+        }
+
+        let cell = &mut self.time_cells[index];
+        cell.clear(vram);
+        let mut text_writer = cell.writer(TEXT_COLOUR, 0, map, vram);
+        write!(&mut text_writer, "{character}").unwrap();
+        text_writer.commit();
+    }
+}
@@ -0,0 +1,133 @@
+use agb::display::object::{Graphics, OamManaged, Object, Tag};
+use agb::display::Priority;
+use agb::fixnum::Vector2D;
+use simulation::sprite::{Animation, Sprite};
+
+pub const WARRIOR_IDLE: &Graphics = agb::include_aseprite!("gfx/warrior/Idle.aseprite");
+pub const WARRIOR_IDLE_ANIMATION: &Tag = WARRIOR_IDLE.tags().get("idle");
+
+pub const WARRIOR_RUN: &Graphics = agb::include_aseprite!("gfx/warrior/Run.aseprite");
+pub const WARRIOR_RUN_ANIMATION: &Tag = WARRIOR_RUN.tags().get("running");
+
+pub const WARRIOR_RUN_ATTACK: &Graphics =
+    agb::include_aseprite!("gfx/warrior/RunningAttack.aseprite");
+pub const WARRIOR_RUN_ATTACK_ANIMATION: &Tag = WARRIOR_RUN_ATTACK.tags().get("attack");
+
+pub const WARRIOR_JUMP: &Graphics = agb::include_aseprite!("gfx/warrior/Jump.aseprite");
+pub const WARRIOR_JUMP_ANIMATION: &Tag = WARRIOR_JUMP.tags().get("Loop");
+
+pub const WARRIOR_DEAD_START: &Graphics = agb::include_aseprite!("gfx/warrior/DeadStart.aseprite");
+pub const WARRIOR_DEAD_START_ANIMATION: &Tag = WARRIOR_DEAD_START.tags().get("dead");
+
+pub const WARRIOR_DEAD_END: &Graphics = agb::include_aseprite!("gfx/warrior/DeadEnd.aseprite");
+pub const WARRIOR_DEAD_END_ANIMATION: &Tag = WARRIOR_DEAD_END.tags().get("dead");
+
+pub const WARRIOR_CLIMB: &Graphics = agb::include_aseprite!("gfx/warrior/Climb.aseprite");
+pub const WARRIOR_CLIMB_ANIMATION: &Tag = WARRIOR_CLIMB.tags().get("climb");
+
+pub const WARRIOR_ATTACK: &Graphics = agb::include_aseprite!("gfx/warrior/NewAttack.aseprite");
+pub const WARRIOR_ATTACK_ANIMATION: &Tag = WARRIOR_ATTACK.tags().get("attack");
+
+pub const BOAR_IDLE: &Graphics = agb::include_aseprite!("gfx/boar/Idle.aseprite");
+pub const BOAR_IDLE_ANIMATION: &Tag = BOAR_IDLE.tags().get("idle");
+
+pub const BOAR_HIT: &Graphics = agb::include_aseprite!("gfx/boar/Hit.aseprite");
+pub const BOAR_HIT_ANIMATION: &Tag = BOAR_HIT.tags().get("hit");
+
+pub const BOAR_RUN: &Graphics = agb::include_aseprite!("gfx/boar/Run.aseprite");
+pub const BOAR_RUN_ANIMATION: &Tag = BOAR_RUN.tags().get("run");
+
+/// OAM, each object has four `u16` attributes
+const OBJECT_ATTRIBUTES: *mut u16 = 0x0700_0000 as *mut u16;
+/// The graphics mode bits of an object's first attribute
+const OBJECT_MODE: u16 = 0b11 << 10;
+const OBJECT_MODE_SEMI_TRANSPARENT: u16 = 0b01 << 10;
+/// Where an object is on screen in its first two attributes
+const OBJECT_Y: u16 = 0xff;
+const OBJECT_X: u16 = 0x1ff;
+
+/// agb 0.18 has no way to set an object's graphics mode, so this makes `sprite` semi-transparent
+/// by changing the object committed to OAM `slot` directly. The next commit makes it opaque
+/// again.
+///
+/// Panics if the object in `slot` isn't where `sprite` is, as then it's some other object
+pub fn set_semi_transparent(slot: usize, sprite: &ObjectSprite) {
+    unsafe {
+        let attribute = OBJECT_ATTRIBUTES.add(slot * 4);
+        let value = attribute.read_volatile();
+        let x = attribute.add(1).read_volatile() & OBJECT_X;
+
+        assert_eq!(
+            (x, value & OBJECT_Y),
+            (
+                sprite.position.x as u16 & OBJECT_X,
+                sprite.position.y as u16 & OBJECT_Y
+            ),
+            "OAM slot {slot} doesn't hold the sprite to make semi-transparent"
+        );
+
+        attribute.write_volatile((value & !OBJECT_MODE) | OBJECT_MODE_SEMI_TRANSPARENT);
+    }
+}
+
+fn tag(animation: Animation) -> &'static Tag {
+    match animation {
+        Animation::WarriorIdle => WARRIOR_IDLE_ANIMATION,
+        Animation::WarriorRun => WARRIOR_RUN_ANIMATION,
+        Animation::WarriorRunAttack => WARRIOR_RUN_ATTACK_ANIMATION,
+        Animation::WarriorJump => WARRIOR_JUMP_ANIMATION,
+        Animation::WarriorClimb => WARRIOR_CLIMB_ANIMATION,
+        Animation::WarriorAttack => WARRIOR_ATTACK_ANIMATION,
+        Animation::BoarIdle => BOAR_IDLE_ANIMATION,
+        Animation::BoarRun => BOAR_RUN_ANIMATION,
+        Animation::BoarHit => BOAR_HIT_ANIMATION,
+    }
+}
+
+/// Shows a simulation entity with an OAM object
+pub struct ObjectSprite<'a, 'gba> {
+    pub object: Object<'a>,
+    controller: &'a OamManaged<'gba>,
+    /// Where the object was last put on screen
+    position: Vector2D<i32>,
+}
+
+impl<'a, 'gba> ObjectSprite<'a, 'gba> {
+    pub fn new(controller: &'a OamManaged<'gba>) -> Self {
+        let mut object = controller.object_sprite(WARRIOR_IDLE.sprites().first().unwrap());
+        object.set_priority(Priority::P1);
+        ObjectSprite {
+            object,
+            controller,
+            position: (0, 0).into(),
+        }
+    }
+}
+
+impl Sprite for ObjectSprite<'_, '_> {
+    fn set_animation(&mut self, animation: Animation, frame: usize) {
+        let sprite = tag(animation).animation_sprite(frame);
+        self.object.set_sprite(self.controller.sprite(sprite));
+    }
+
+    fn frames(&self, animation: Animation) -> usize {
+        tag(animation).sprites().len()
+    }
+
+    fn set_hflip(&mut self, flip: bool) {
+        self.object.set_hflip(flip);
+    }
+
+    fn set_position(&mut self, position: Vector2D<i32>) {
+        self.object.set_position(position);
+        self.position = position;
+    }
+
+    fn show(&mut self) {
+        self.object.show();
+    }
+
+    fn hide(&mut self) {
+        self.object.hide();
+    }
+}
@@ -0,0 +1,134 @@
+use agb::display::blend::{Blend, BlendMode, Layer};
+use agb::display::tiled::BackgroundID;
+use agb::fixnum::Num;
+use agb::interrupt::VBlank;
+
+/// Frames between each of the 16 steps of a fade
+const FRAMES_PER_STEP: u32 = 2;
+const FADE_STEPS: u8 = 16;
+/// Biggest mosaic block size, the register holds one less than the size in pixels
+const MAX_MOSAIC: u16 = 15;
+/// Semi-transparent objects are half their own colour and half what's behind them
+const TRANSLUCENT_WEIGHT: u8 = FADE_STEPS / 2;
+
+const MOSAIC: *mut u16 = 0x0400_004C as *mut u16;
+/// BG0CNT, the other three background control registers follow it
+const BACKGROUND_CONTROL: *mut u16 = 0x0400_0008 as *mut u16;
+const BACKGROUND_CONTROL_MOSAIC: u16 = 1 << 6;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TransitionStyle {
+    /// Fade to or from black
+    Black,
+    /// Fade to or from white, for finishing a level
+    White,
+    /// Pixelate the backgrounds while fading to black, for when the player dies
+    Mosaic,
+}
+
+/// Fades the screen using the blend brightness registers so backgrounds can be swapped
+/// while nothing can be seen
+pub struct Transition<'gba> {
+    blend: Blend<'gba>,
+}
+
+impl<'gba> Transition<'gba> {
+    pub fn new(blend: Blend<'gba>) -> Self {
+        Transition { blend }
+    }
+
+    /// Fades the given backgrounds, all sprites and the backdrop out, leaving the screen
+    /// fully faded until [Transition::fade_in] is called
+    pub fn fade_out(
+        &mut self,
+        vblank: &VBlank,
+        style: TransitionStyle,
+        backgrounds: &[BackgroundID],
+    ) {
+        self.set_targets(style, backgrounds);
+
+        for step in 0..=FADE_STEPS {
+            self.show_step(vblank, style, step);
+        }
+    }
+
+    /// Fades the given backgrounds, all sprites and the backdrop in from fully faded
+    pub fn fade_in(
+        &mut self,
+        vblank: &VBlank,
+        style: TransitionStyle,
+        backgrounds: &[BackgroundID],
+    ) {
+        self.set_targets(style, backgrounds);
+
+        for step in (0..=FADE_STEPS).rev() {
+            self.show_step(vblank, style, step);
+        }
+
+        self.blend.reset().commit();
+    }
+
+    /// Lets the given backgrounds and the backdrop show through semi-transparent objects.
+    /// Fading turns it off again
+    pub fn show_translucent_objects(&mut self, backgrounds: &[BackgroundID]) {
+        self.blend.reset().set_blend_mode(BlendMode::Normal);
+        self.blend
+            .layer(Layer::Top)
+            .set_blend_weight(Num::from_raw(TRANSLUCENT_WEIGHT));
+
+        let mut bottom = self.blend.layer(Layer::Bottom);
+        for &background in backgrounds {
+            bottom.set_background_enable(background, true);
+        }
+        bottom
+            .set_backdrop_enable(true)
+            .set_blend_weight(Num::from_raw(TRANSLUCENT_WEIGHT));
+
+        self.blend.commit();
+    }
+
+    fn set_targets(&mut self, style: TransitionStyle, backgrounds: &[BackgroundID]) {
+        let blend_mode = match style {
+            TransitionStyle::White => BlendMode::FadeToWhite,
+            TransitionStyle::Black | TransitionStyle::Mosaic => BlendMode::FadeToBlack,
+        };
+
+        self.blend.reset().set_blend_mode(blend_mode);
+
+        let mut top = self.blend.layer(Layer::Top);
+        for &background in backgrounds {
+            top.set_background_enable(background, true);
+        }
+        top.set_object_enable(true).set_backdrop_enable(true);
+    }
+
+    fn show_step(&mut self, vblank: &VBlank, style: TransitionStyle, step: u8) {
+        for _ in 0..FRAMES_PER_STEP {
+            vblank.wait_for_vblank();
+        }
+
+        self.blend.set_fade(Num::from_raw(step)).commit();
+
+        if style == TransitionStyle::Mosaic {
+            set_mosaic(MAX_MOSAIC * step as u16 / FADE_STEPS as u16);
+        }
+    }
+}
+
+/// agb doesn't support background mosaic, so this turns it on for every background
+/// directly. Committing a background turns it back off for that background
+fn set_mosaic(size: u16) {
+    unsafe {
+        MOSAIC.write_volatile(size | (size << 4));
+
+        for background in 0..4 {
+            let control = BACKGROUND_CONTROL.add(background);
+            let value = control.read_volatile();
+            if size == 0 {
+                control.write_volatile(value & !BACKGROUND_CONTROL_MOSAIC);
+            } else {
+                control.write_volatile(value | BACKGROUND_CONTROL_MOSAIC);
+            }
+        }
+    }
+}
@@ -1,4 +0,0 @@
-use agb::fixnum::FixedNum;
-
-pub type FixedNumberType = FixedNum<10>;
-pub const TILE_SIZE: u32 = 8;
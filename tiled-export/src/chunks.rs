@@ -0,0 +1,69 @@
+//! Layers are written out as a grid of chunks of tiles where each different chunk is only
+//! stored once, so the empty and repeated parts of a level take up very little ROM while any
+//! tile can still be looked up straight away
+
+use crate::level::Layer;
+pub use simulation::chunks::CHUNK_SIZE;
+use std::collections::HashMap;
+
+pub const CHUNK_TILES: usize = CHUNK_SIZE * CHUNK_SIZE;
+/// `CHUNK_SIZE` in the same type as the exporter's tile coordinates
+const SIZE: i32 = CHUNK_SIZE as i32;
+
+pub struct ChunkedLayer {
+    /// In chunks, levels that aren't a whole number of chunks are padded with empty tiles
+    pub width: i32,
+    pub height: i32,
+    /// The index in `chunks` of every chunk of the layer, a row at a time
+    pub chunk_map: Vec<u16>,
+    /// The tiles of each different chunk, a row at a time
+    pub chunks: Vec<[u16; CHUNK_TILES]>,
+}
+
+impl ChunkedLayer {
+    pub fn new(layer: &Layer) -> Self {
+        Self::from_tiles(layer.width(), layer.height(), &layer.tiles)
+    }
+
+    /// Chunks any grid of `tiles` a row at a time, `tiles_width` by `tiles_height` in size
+    pub fn from_tiles(tiles_width: i32, tiles_height: i32, tiles: &[u16]) -> Self {
+        let width = (tiles_width + SIZE - 1) / SIZE;
+        let height = (tiles_height + SIZE - 1) / SIZE;
+
+        let mut chunk_map = Vec::with_capacity((width * height) as usize);
+        let mut chunks = vec![];
+        let mut chunk_ids = HashMap::new();
+
+        for chunk_y in 0..height {
+            for chunk_x in 0..width {
+                let mut chunk = [0; CHUNK_TILES];
+                for (index, tile) in chunk.iter_mut().enumerate() {
+                    let x = chunk_x * SIZE + index as i32 % SIZE;
+                    let y = chunk_y * SIZE + index as i32 / SIZE;
+                    if x < tiles_width && y < tiles_height {
+                        *tile = tiles[(y * tiles_width + x) as usize];
+                    }
+                }
+
+                let id = *chunk_ids.entry(chunk).or_insert_with(|| {
+                    chunks.push(chunk);
+                    u16::try_from(chunks.len() - 1).expect("Too many different chunks in a layer")
+                });
+                chunk_map.push(id);
+            }
+        }
+
+        ChunkedLayer {
+            width,
+            height,
+            chunk_map,
+            chunks,
+        }
+    }
+
+    /// The tile at `x`, `y` the same way the game looks it up
+    pub fn tile(&self, x: i32, y: i32) -> u16 {
+        let chunk = self.chunk_map[((y / SIZE) * self.width + x / SIZE) as usize];
+        self.chunks[chunk as usize][((y % SIZE) * SIZE + x % SIZE) as usize]
+    }
+}
@@ -0,0 +1,68 @@
+//! Both layers' tile flags and hit masks baked into one grid for the level, so the game can
+//! check what's at a tile with a single lookup instead of going through each layer's tiles and
+//! tile set.
+//!
+//! Both layers can use any tile flag, and what's at a tile is every flag of either layer whose
+//! hit mask overlaps the pixels being checked. Empty cells have no flags on either layer
+
+use crate::chunks::ChunkedLayer;
+use crate::level::Level;
+use std::collections::HashMap;
+
+/// A layer's flags at a tile and the pixels of the tile they apply to, already flipped the
+/// way the tile is
+pub type LayerCollision = (u32, u64);
+
+pub struct CollisionGrid {
+    /// In tiles, the same as the level
+    pub width: i32,
+    pub height: i32,
+    /// The index in `tiles` of every tile of the level, a row at a time
+    pub grid: Vec<u16>,
+    /// Each different pair of background and foreground collisions in the level
+    pub tiles: Vec<[LayerCollision; 2]>,
+}
+
+impl CollisionGrid {
+    pub fn new(level: &Level) -> Self {
+        let mut grid = Vec::with_capacity((level.width * level.height) as usize);
+        let mut tiles = vec![];
+        let mut tile_ids = HashMap::new();
+
+        for y in 0..level.height {
+            for x in 0..level.width {
+                let tile = [level.background.tile(x, y), level.foreground.tile(x, y)].map(
+                    // Tiles without flags don't collide with anything, whatever their shape
+                    |(flags, mask)| if flags == 0 { (0, 0) } else { (flags, mask) },
+                );
+
+                let id = *tile_ids.entry(tile).or_insert_with(|| {
+                    tiles.push(tile);
+                    u16::try_from(tiles.len() - 1).expect("Too many different tiles in a level")
+                });
+                grid.push(id);
+            }
+        }
+
+        CollisionGrid {
+            width: level.width,
+            height: level.height,
+            grid,
+            tiles,
+        }
+    }
+
+    /// The grid chunked the same way as the layers, which is how the game stores it
+    pub fn chunked(&self) -> ChunkedLayer {
+        ChunkedLayer::from_tiles(self.width, self.height, &self.grid)
+    }
+
+    /// The flags of both layers at `x`, `y` where their hit masks overlap `area`, the same way
+    /// the game checks them
+    pub fn flags_in_area(&self, x: i32, y: i32, area: u64) -> u32 {
+        self.tiles[self.grid[(y * self.width + x) as usize] as usize]
+            .iter()
+            .filter(|(_, mask)| mask & area != 0)
+            .fold(0, |flags, (layer_flags, _)| flags | layer_flags)
+    }
+}
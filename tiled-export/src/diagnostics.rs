@@ -0,0 +1,126 @@
+use crate::tiled::{Layers, TiledObject};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A problem with a level along with where in the level it is
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: String,
+    pub layer: Option<String>,
+    pub object_id: Option<i32>,
+    /// In pixels
+    pub position: Option<(i32, i32)>,
+    /// In tiles
+    pub tile: Option<(i32, i32)>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub(crate) fn layer(&mut self, layer: &Layers) -> &mut Self {
+        self.layer = Some(format!("{layer:?}"));
+        self
+    }
+
+    pub(crate) fn object(&mut self, object: &TiledObject) -> &mut Self {
+        self.object_id = Some(object.id);
+        self.layer(&Layers::Objects).at(object.x, object.y)
+    }
+
+    pub(crate) fn at(&mut self, x: i32, y: i32) -> &mut Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    pub(crate) fn tile(&mut self, x: i32, y: i32) -> &mut Self {
+        self.tile = Some((x, y));
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let Some(layer) = &self.layer {
+            write!(f, ", layer {layer}")?;
+        }
+        if let Some(object_id) = self.object_id {
+            write!(f, ", object {object_id}")?;
+        }
+        if let Some((x, y)) = self.position {
+            write!(f, " at ({x}, {y})")?;
+        }
+        if let Some((x, y)) = self.tile {
+            write!(f, " at tile ({x}, {y})")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Collects problems with the levels so they can all be reported at once rather than
+/// stopping the build at the first one
+#[derive(Default)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub(crate) fn error(&mut self, file: &str, message: impl Into<String>) -> &mut Diagnostic {
+        self.push(Severity::Error, file, message.into())
+    }
+
+    pub(crate) fn warning(&mut self, file: &str, message: impl Into<String>) -> &mut Diagnostic {
+        self.push(Severity::Warning, file, message.into())
+    }
+
+    fn push(&mut self, severity: Severity, file: &str, message: String) -> &mut Diagnostic {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            file: file.to_string(),
+            layer: None,
+            object_id: None,
+            position: None,
+            tile: None,
+            message,
+        });
+        self.diagnostics.last_mut().unwrap()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Warning)
+    }
+
+    /// Prints the warnings as cargo warnings, which cargo already labels, then fails the build
+    /// if there were any errors. The errors go to stderr on their own so cargo shows them with
+    /// the failed build rather than as warnings
+    pub fn report(&self) {
+        for warning in self.warnings() {
+            println!("cargo:warning={warning}");
+        }
+
+        let error_count = self.errors().count();
+        if error_count == 0 {
+            return;
+        }
+
+        for error in self.errors() {
+            eprintln!("error: {error}");
+        }
+        panic!("{error_count} problems with the levels");
+    }
+}
@@ -0,0 +1,378 @@
+use crate::chunks::{ChunkedLayer, CHUNK_TILES};
+use crate::collision::{CollisionGrid, LayerCollision};
+use crate::level::Level;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Error, ErrorKind, Write};
+use std::mem::size_of;
+use std::path::Path;
+
+/// Writes `{out_dir}/{level.file}.rs`, the level's data and a `get_level` function for the
+/// game to include
+pub fn write_level(out_dir: &Path, level: &Level) -> std::io::Result<()> {
+    let level_file = &level.file;
+    let output_file = File::create(out_dir.join(format!("{level_file}.rs")))?;
+    let mut writer = BufWriter::new(output_file);
+
+    writeln!(&mut writer, "const WIDTH: u32 = {};", level.width)?;
+    writeln!(&mut writer, "const HEIGHT: u32 = {};", level.height)?;
+    writeln!(
+        &mut writer,
+        "const FOREGROUND: ChunkedLayer = {};",
+        format_chunked_layer(&ChunkedLayer::new(&level.foreground))
+    )?;
+    writeln!(
+        &mut writer,
+        "const BACKGROUND: ChunkedLayer = {};",
+        format_chunked_layer(&ChunkedLayer::new(&level.background))
+    )?;
+
+    let position = |position: &(i32, i32)| format!("({}, {})", position.0, position.1);
+    let slimes_str = join(&level.slimes, position);
+    let snails_str = join(&level.snails, position);
+    let boars_str = join(&level.boars, position);
+    let enemy_stop_str = join(&level.enemy_stops, position);
+    let camera_bounds_str = join(&level.camera_bounds, |bounds| {
+        format!("({}, {}, {}, {})", bounds.0, bounds.1, bounds.2, bounds.3)
+    });
+
+    writeln!(
+        &mut writer,
+        "const SNAILS: &[(i32, i32)] = &[{snails_str}];",
+    )?;
+    writeln!(
+        &mut writer,
+        "const SLIMES: &[(i32, i32)] = &[{slimes_str}];",
+    )?;
+    writeln!(&mut writer, "const BOARS: &[(i32, i32)] = &[{boars_str}];",)?;
+    writeln!(
+        &mut writer,
+        "const ENEMY_STOPS: &[(i32, i32)] = &[{enemy_stop_str}];",
+    )?;
+    writeln!(
+        &mut writer,
+        "const CAMERA_BOUNDS: &[(i32, i32, i32, i32)] = &[{camera_bounds_str}];",
+    )?;
+    writeln!(
+        &mut writer,
+        "const START_POS: (i32, i32) = ({}, {});",
+        level.player_start.0, level.player_start.1
+    )?;
+
+    let metadata = &level.metadata;
+    writeln!(&mut writer, "const NAME: &str = {:?};", metadata.name)?;
+    writeln!(
+        &mut writer,
+        "const SUBTITLE: Option<&str> = {};",
+        format_option(
+            metadata
+                .subtitle
+                .as_ref()
+                .map(|subtitle| format!("{subtitle:?}"))
+        )
+    )?;
+    writeln!(
+        &mut writer,
+        "const INTRO: Option<&str> = {};",
+        format_option(metadata.intro.as_ref().map(|intro| format!("{intro:?}")))
+    )?;
+    writeln!(
+        &mut writer,
+        "const PAR_TIME: Option<i32> = {};",
+        format_option(metadata.par_time.map(|par_time| par_time.to_string()))
+    )?;
+    writeln!(
+        &mut writer,
+        "const MUSIC_ID: Option<u32> = {};",
+        format_option(metadata.music_id.map(|music_id| music_id.to_string()))
+    )?;
+    writeln!(
+        &mut writer,
+        "const BACKGROUND_COLOUR: u16 = {:#06x};",
+        get_gba_colour(&metadata.background_colour)
+    )?;
+
+    let collision = CollisionGrid::new(level);
+    writeln!(
+        &mut writer,
+        "const COLLISION: ChunkedLayer = {};",
+        format_chunked_layer(&collision.chunked())
+    )?;
+    writeln!(
+        &mut writer,
+        "const COLLISION_TILES: &[[(u32, u64); 2]] = &[{}];",
+        join(&collision.tiles, |[background, foreground]| format!(
+            "[({}, {:#018x}), ({}, {:#018x})]",
+            background.0, background.1, foreground.0, foreground.1
+        ))
+    )?;
+
+    writeln!(
+        &mut writer,
+        r#"
+            use crate::level::{{ChunkedLayer, Level}};
+            use crate::level::map_tiles::assets::{level_file} as graphics;
+            use agb::fixnum::Vector2D;
+            use simulation::world::World;
+
+            pub const fn get_level() -> Level<'static> {{
+                Level {{
+                    background: BACKGROUND,
+                    foreground: FOREGROUND,
+                    world: World {{
+                        dimensions: Vector2D {{x: WIDTH, y: HEIGHT}},
+                        collision: COLLISION,
+                        collision_tiles: COLLISION_TILES,
+
+                        enemy_stops: ENEMY_STOPS,
+                        camera_bounds: CAMERA_BOUNDS,
+                        slimes: SLIMES,
+                        snails: SNAILS,
+                        boars: BOARS,
+                        start_pos: START_POS,
+                    }},
+                    name: NAME,
+                    subtitle: SUBTITLE,
+                    intro: INTRO,
+                    par_time: PAR_TIME,
+                    music_id: MUSIC_ID,
+                    background_colour: BACKGROUND_COLOUR,
+                    palettes: graphics::PALETTES,
+                    background_tile_set: graphics::background.tiles,
+                    background_tile_settings: graphics::background.tile_settings,
+                    foreground_tile_set: graphics::foreground.tiles,
+                    foreground_tile_settings: graphics::foreground.tile_settings,
+                }}
+            }}
+            "#
+    )?;
+
+    Ok(())
+}
+
+/// Roughly how much ROM a level uses, in bytes
+pub struct RomSize {
+    pub level: String,
+    /// The `assets` module the level's tile set graphics are in
+    pub tile_set: String,
+    /// After duplicate tiles are removed
+    pub tile_graphics: usize,
+    /// The layers, collision and objects
+    pub level_data: usize,
+}
+
+impl RomSize {
+    pub fn total(&self) -> usize {
+        self.tile_graphics + self.level_data
+    }
+}
+
+impl fmt::Display for RomSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} uses {} bytes of ROM, {} of tile graphics in {} and {} of level data",
+            self.level,
+            self.total(),
+            self.tile_graphics,
+            self.tile_set,
+            self.level_data
+        )
+    }
+}
+
+/// How much ROM every level uses, a line for each level and then the total
+pub struct RomSizes(pub Vec<RomSize>);
+
+impl RomSizes {
+    /// Tile graphics shared by several levels are only counted once
+    pub fn total(&self) -> usize {
+        let mut tile_sets = HashSet::new();
+        self.0
+            .iter()
+            .map(|size| {
+                if tile_sets.insert(&size.tile_set) {
+                    size.total()
+                } else {
+                    size.level_data
+                }
+            })
+            .sum()
+    }
+}
+
+impl fmt::Display for RomSizes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for size in &self.0 {
+            writeln!(f, "{size}")?;
+        }
+        writeln!(f, "The levels use {} bytes of ROM in total", self.total())
+    }
+}
+
+/// Writes `{out_dir}/assets.rs`, the one module every level's tile set graphics are included
+/// in. Levels whose images are the same share a `tile_set_{n}` module so they're only put in
+/// the ROM once, and each level has its own name for it. `graphics_directory` is relative to
+/// the game's crate, which build scripts are run in.
+///
+/// Returns roughly how much ROM each level uses
+pub fn write_assets(
+    out_dir: &Path,
+    graphics_directory: &str,
+    levels: &[Level],
+) -> std::io::Result<RomSizes> {
+    let output_file = File::create(out_dir.join("assets.rs"))?;
+    let mut writer = BufWriter::new(output_file);
+
+    // The tile set module and its size for each different transparent colour and pair of images
+    let mut tile_sets = HashMap::<_, (String, usize)>::new();
+    let mut sizes = vec![];
+    for level in levels {
+        let level_file = &level.file;
+        // Both tile sets have the same transparent colour or the level doesn't load
+        let transparent_colour = &level.background.tile_set.transparent_colour;
+        let [background_image, foreground_image] = tile_set_images(graphics_directory, level);
+        let images = [
+            std::fs::read(&background_image)?,
+            std::fs::read(&foreground_image)?,
+        ];
+
+        let key = (transparent_colour.clone(), images);
+        let (tile_set, tile_graphics) = match tile_sets.get(&key) {
+            Some(tile_set) => tile_set.clone(),
+            None => {
+                let tile_set = format!("tile_set_{}", tile_sets.len());
+                writeln!(
+                    &mut writer,
+                    r#"
+                    pub mod {tile_set} {{
+                        agb::include_background_gfx!(
+                            gfx, "{transparent_colour}",
+                            background => 16 deduplicate "{background_image}",
+                            foreground => 16 deduplicate "{foreground_image}"
+                        );
+                        pub use gfx::{{background, foreground, PALETTES}};
+                    }}
+                    "#
+                )?;
+
+                let tile_graphics = get_image_tile_size(&background_image, transparent_colour)?
+                    + get_image_tile_size(&foreground_image, transparent_colour)?;
+                tile_sets.insert(key, (tile_set.clone(), tile_graphics));
+                (tile_set, tile_graphics)
+            }
+        };
+        writeln!(&mut writer, "pub use {tile_set} as {level_file};")?;
+
+        sizes.push(RomSize {
+            level: level_file.clone(),
+            tile_set,
+            tile_graphics,
+            level_data: get_level_data_size(level),
+        });
+    }
+
+    Ok(RomSizes(sizes))
+}
+
+/// The background and foreground images `write_assets` includes for the level, relative to
+/// the game's crate like `graphics_directory`
+pub fn tile_set_images(graphics_directory: &str, level: &Level) -> [String; 2] {
+    let level_file = &level.file;
+    ["background", "foreground"]
+        .map(|layer| format!("{graphics_directory}/{level_file}/{level_file}_{layer}.png"))
+}
+
+/// Bytes of map, collision and object data in the generated level module
+fn get_level_data_size(level: &Level) -> usize {
+    let collision = CollisionGrid::new(level);
+    let objects =
+        level.snails.len() + level.slimes.len() + level.boars.len() + level.enemy_stops.len();
+    let chunked_size = |layer: &ChunkedLayer| {
+        layer.chunk_map.len() * size_of::<u16>()
+            + layer.chunks.len() * size_of::<[u16; CHUNK_TILES]>()
+    };
+
+    chunked_size(&ChunkedLayer::new(&level.background))
+        + chunked_size(&ChunkedLayer::new(&level.foreground))
+        + chunked_size(&collision.chunked())
+        + collision.tiles.len() * size_of::<[LayerCollision; 2]>()
+        + objects * 2 * size_of::<i32>()
+        + level.camera_bounds.len() * 4 * size_of::<i32>()
+}
+
+/// Bytes the image takes as 4bpp tiles once duplicates are removed. Tiles that are the same
+/// when flipped are duplicates too, and every transparent pixel is the same whatever its
+/// colour, the same as `include_background_gfx!` treats them
+fn get_image_tile_size(filename: &str, transparent_colour: &str) -> std::io::Result<usize> {
+    let image = image::open(filename)
+        .map_err(|error| Error::new(ErrorKind::InvalidData, format!("{filename}: {error}")))?
+        .to_rgba8();
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let transparent = get_rgb(transparent_colour);
+
+    let mut tiles = HashSet::new();
+    for tile_y in 0..height / 8 {
+        for tile_x in 0..width / 8 {
+            let pixel = |x: usize, y: usize| {
+                let [red, green, blue, alpha] = image
+                    .get_pixel((tile_x * 8 + x) as u32, (tile_y * 8 + y) as u32)
+                    .0;
+                if alpha == 0 || [red, green, blue] == transparent {
+                    [0; 4]
+                } else {
+                    [red, green, blue, alpha]
+                }
+            };
+            let flips = [(false, false), (true, false), (false, true), (true, true)].map(
+                |(hflip, vflip)| -> [[u8; 4]; 64] {
+                    std::array::from_fn(|index| {
+                        let (x, y) = (index % 8, index / 8);
+                        pixel(if hflip { 7 - x } else { x }, if vflip { 7 - y } else { y })
+                    })
+                },
+            );
+            tiles.insert(flips.into_iter().min().unwrap());
+        }
+    }
+
+    Ok(tiles.len() * 32)
+}
+
+/// Splits an `rrggbb` colour into its red, green and blue
+fn get_rgb(rgb: &str) -> [u8; 3] {
+    let value = u32::from_str_radix(rgb, 16).expect("Colour should already be checked");
+    [(value >> 16) as u8, (value >> 8) as u8, value as u8]
+}
+
+/// Converts an `rrggbb` colour to the GBA's 15 bit colour
+fn get_gba_colour(rgb: &str) -> u16 {
+    let [red, green, blue] = get_rgb(rgb).map(u32::from);
+
+    ((red >> 3) | ((green >> 3) << 5) | ((blue >> 3) << 10)) as u16
+}
+
+/// A `ChunkedLayer` for the game, like `ChunkedLayer { width: 1, chunk_map: &[0], chunks: &[[...]] }`
+fn format_chunked_layer(layer: &ChunkedLayer) -> String {
+    format!(
+        "ChunkedLayer {{ width: {}, chunk_map: &[{}], chunks: &[{}] }}",
+        layer.width,
+        join(&layer.chunk_map, |chunk| chunk.to_string()),
+        join(&layer.chunks, |chunk| format!(
+            "[{}]",
+            join(chunk, |tile| tile.to_string())
+        ))
+    )
+}
+
+fn join<T>(values: &[T], format: impl Fn(&T) -> String) -> String {
+    values.iter().map(format).collect::<Vec<_>>().join(", ")
+}
+
+fn format_option(value: Option<String>) -> String {
+    match value {
+        Some(value) => format!("Some({value})"),
+        None => "None".to_string(),
+    }
+}
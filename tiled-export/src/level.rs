@@ -0,0 +1,666 @@
+use crate::diagnostics::Diagnostics;
+use crate::tile_ids::{self, FLIP_BITS};
+use crate::tile_set::{TileSet, FULL_TILE_MASK};
+use crate::tiled::{read_map, Layers, TiledLayer, TiledLevel, TiledObject};
+use crate::tilemap::{COLLISION_TILE, KILL_TILE, WIN_TILE};
+use crate::{map_tile, TILE_PIXELS};
+use simulation::entity::pixel_area;
+use std::path::{Path, PathBuf};
+
+/// Size of the GBA screen in tiles
+const SCREEN_TILES: (i32, i32) = (30, 20);
+/// Positions are `FixedNum<10>` in game, so anything past this many tiles can't be reached
+const MAX_LEVEL_TILES: i32 = (1 << 21) / TILE_PIXELS;
+
+/// Frames per second on the GBA, par times are given in seconds in Tiled
+const FRAMES_PER_SECOND: f64 = 60.0;
+/// Used when neither the map or its properties give a background colour
+const DEFAULT_BACKGROUND_COLOUR: &str = "2ce8f4";
+
+/// A level read from Tiled and checked over, ready to be written out for the game
+pub struct Level {
+    /// The name of the level's directory and files, like `level_1`
+    pub file: String,
+    /// In tiles
+    pub width: i32,
+    pub height: i32,
+    pub background: Layer,
+    pub foreground: Layer,
+    pub snails: Vec<(i32, i32)>,
+    pub slimes: Vec<(i32, i32)>,
+    pub boars: Vec<(i32, i32)>,
+    pub enemy_stops: Vec<(i32, i32)>,
+    /// `(x, y, width, height)` in pixels
+    pub camera_bounds: Vec<(i32, i32, i32, i32)>,
+    pub player_start: (i32, i32),
+    pub metadata: LevelMetadata,
+    /// The map and tile set files the level was read from
+    pub sources: Vec<PathBuf>,
+}
+
+/// A tile layer with its Tiled ids turned into ids in its own tile set, with the flip bits
+/// the game expects on top
+pub struct Layer {
+    /// See [map_tile](crate::map_tile)
+    pub tiles: Vec<u16>,
+    pub tile_set: TileSet,
+    /// Cells with no tile are shown as the tile set's first tile, but never have its flags
+    empty: Vec<bool>,
+    width: i32,
+}
+
+pub struct LevelMetadata {
+    pub name: String,
+    pub subtitle: Option<String>,
+    pub intro: Option<String>,
+    /// In frames
+    pub par_time: Option<i32>,
+    pub music_id: Option<u32>,
+    /// As `rrggbb` hex, the colour of the backdrop behind both layers
+    pub background_colour: String,
+}
+
+/// The level's map, `{map_directory}/{level_file}/{level_file}_map.tmx` or a JSON export
+/// of it if there's no `.tmx`
+pub fn map_path(map_directory: &Path, level_file: &str) -> PathBuf {
+    let path = map_directory
+        .join(level_file)
+        .join(format!("{level_file}_map.tmx"));
+
+    if path.exists() {
+        path
+    } else {
+        path.with_extension("json")
+    }
+}
+
+/// Reads the level's map and the tile sets it uses. Anything wrong with the level is added
+/// to `diagnostics`, giving `None` if it can't be used
+pub fn load_level(
+    map_directory: &Path,
+    level_file: &str,
+    diagnostics: &mut Diagnostics,
+) -> Option<Level> {
+    let path = map_path(map_directory, level_file);
+    let filename = &path.display().to_string();
+
+    let level = read_map(&path, diagnostics)?;
+
+    if !check_level_dimensions(filename, &level, diagnostics) {
+        return None;
+    }
+
+    let background_tileset = get_tileset(filename, &level, "background", diagnostics);
+    let foreground_tileset = get_tileset(filename, &level, "foreground", diagnostics);
+    let world = get_layer(filename, &level, Layers::World, diagnostics);
+    let world_objects = get_layer(filename, &level, Layers::WorldObjects, diagnostics);
+    let objects_layer = get_layer(filename, &level, Layers::Objects, diagnostics);
+
+    let (
+        Some(background_tileset),
+        Some(foreground_tileset),
+        Some(world),
+        Some(world_objects),
+        Some(objects_layer),
+    ) = (
+        background_tileset,
+        foreground_tileset,
+        world,
+        world_objects,
+        objects_layer,
+    )
+    else {
+        return None;
+    };
+
+    // Tile set sources are relative to the map
+    let map_folder = path.parent().unwrap_or(map_directory);
+    let tile_set_path = |tileset: usize| map_folder.join(&level.tilesets[tileset].source);
+    let sources = vec![
+        path.clone(),
+        tile_set_path(background_tileset),
+        tile_set_path(foreground_tileset),
+    ];
+    let background_tiles = TileSet::load(&sources[1], diagnostics);
+    let foreground_tiles = TileSet::load(&sources[2], diagnostics);
+    let (Some(background_tiles), Some(foreground_tiles)) = (background_tiles, foreground_tiles)
+    else {
+        return None;
+    };
+
+    let background = Layer::new(
+        filename,
+        &level,
+        world,
+        background_tileset,
+        background_tiles,
+        diagnostics,
+    );
+    let foreground = Layer::new(
+        filename,
+        &level,
+        world_objects,
+        foreground_tileset,
+        foreground_tiles,
+        diagnostics,
+    );
+    let (Some(background), Some(foreground)) = (background, foreground) else {
+        return None;
+    };
+
+    // Both tile sets are converted together so they can share palettes
+    if background.tile_set.transparent_colour != foreground.tile_set.transparent_colour {
+        diagnostics.error(
+            filename,
+            format!(
+                "The background tile set's transparent colour is {} but the foreground's is {}, \
+                 they need to be the same",
+                background.tile_set.transparent_colour, foreground.tile_set.transparent_colour
+            ),
+        );
+    }
+
+    let Some(objects_from_file) = &objects_layer.objects else {
+        diagnostics
+            .error(filename, "Expected an object layer")
+            .layer(&Layers::Objects);
+        return None;
+    };
+
+    let mut snails = vec![];
+    let mut slimes = vec![];
+    let mut boars = vec![];
+    let mut enemy_stops = vec![];
+    let mut camera_bounds = vec![];
+    let mut player_start = None;
+
+    for object in objects_from_file {
+        let (x, y) = (object.x, object.y);
+        match object.object_type.as_str() {
+            "Snail Spawn" => snails.push((x, y)),
+            "Slime Spawn" => slimes.push((x, y)),
+            "Boar Spawn" => boars.push((x, y)),
+            "Player Start" => {
+                if player_start.is_some() {
+                    diagnostics
+                        .warning(filename, "More than one Player Start, using the last one")
+                        .object(object);
+                }
+                player_start = Some((x, y));
+            }
+            "Enemy Stop" => enemy_stops.push((x, y)),
+            "Camera Bounds" => camera_bounds.push((x, y, object.width, object.height)),
+            object_type => {
+                diagnostics
+                    .error(filename, format!("Unknown object type {object_type:?}"))
+                    .object(object);
+                continue;
+            }
+        }
+
+        if object.object_type.ends_with("Spawn") || object.object_type == "Player Start" {
+            check_spawn(filename, object, &[&background, &foreground], diagnostics);
+        }
+    }
+
+    let Some(player_start) = player_start else {
+        diagnostics
+            .error(filename, "Need a Player Start object for the player")
+            .layer(&Layers::Objects);
+        return None;
+    };
+
+    check_win_reachable(
+        filename,
+        &level,
+        player_start,
+        &[&background, &foreground],
+        diagnostics,
+    );
+
+    let metadata = get_level_metadata(level_file, filename, &level, diagnostics);
+
+    Some(Level {
+        file: level_file.to_string(),
+        width: level.width,
+        height: level.height,
+        background,
+        foreground,
+        snails,
+        slimes,
+        boars,
+        enemy_stops,
+        camera_bounds,
+        player_start,
+        metadata,
+        sources,
+    })
+}
+
+impl Layer {
+    fn new(
+        filename: &str,
+        level: &TiledLevel,
+        layer: &TiledLayer,
+        tileset: usize,
+        tile_set: TileSet,
+        diagnostics: &mut Diagnostics,
+    ) -> Option<Self> {
+        let Some(data) = &layer.data else {
+            diagnostics
+                .error(filename, "Expected a tile layer")
+                .layer(&layer.name);
+            return None;
+        };
+
+        let tile_count = tile_set.len() as u32;
+        if tile_count > map_tile::ID_MASK as u32 + 1 {
+            diagnostics
+                .error(
+                    filename,
+                    format!(
+                        "{} has {tile_count} tiles, layers can only use {}",
+                        level.tilesets[tileset].source,
+                        map_tile::ID_MASK as u32 + 1
+                    ),
+                )
+                .layer(&layer.name);
+            return None;
+        }
+
+        let first_ids = level
+            .tilesets
+            .iter()
+            .map(|tileset| tileset.firstgid)
+            .collect::<Vec<_>>();
+
+        let mut tiles = Vec::with_capacity(data.len());
+        let mut empty = Vec::with_capacity(data.len());
+        for (index, &gid) in data.iter().enumerate() {
+            let (x, y) = (index as i32 % level.width, index as i32 / level.width);
+            let Some(tile) = tile_ids::decode_gid(gid, &first_ids) else {
+                if gid & !FLIP_BITS != 0 {
+                    diagnostics
+                        .error(filename, format!("Tile id {gid} isn't in any tile set"))
+                        .layer(&layer.name)
+                        .tile(x, y);
+                }
+                tiles.push(0);
+                empty.push(true);
+                continue;
+            };
+
+            if tile.tileset != tileset {
+                diagnostics
+                    .error(
+                        filename,
+                        format!(
+                            "Uses a tile from {} but this layer can only use {}",
+                            level.tilesets[tile.tileset].source, level.tilesets[tileset].source
+                        ),
+                    )
+                    .layer(&layer.name)
+                    .tile(x, y);
+            } else if tile.id >= tile_count {
+                diagnostics
+                    .error(
+                        filename,
+                        format!(
+                            "Tile id {} is past the end of {}, which has {tile_count} tiles",
+                            tile.id, level.tilesets[tileset].source
+                        ),
+                    )
+                    .layer(&layer.name)
+                    .tile(x, y);
+            }
+
+            if tile.diagonal_flip {
+                diagnostics
+                    .warning(
+                        filename,
+                        "Rotated tiles can't be shown on the GBA, only flipped ones",
+                    )
+                    .layer(&layer.name)
+                    .tile(x, y);
+            }
+
+            let mut map_tile = tile.id as u16 & map_tile::ID_MASK;
+            if tile.hflip {
+                map_tile |= map_tile::HFLIP;
+            }
+            if tile.vflip {
+                map_tile |= map_tile::VFLIP;
+            }
+            tiles.push(map_tile);
+            empty.push(false);
+        }
+
+        Some(Layer {
+            tiles,
+            tile_set,
+            empty,
+            width: level.width,
+        })
+    }
+
+    /// In tiles
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.tiles.len() as i32 / self.width
+    }
+
+    /// The flags of the tile at the given tile position and its hit mask, flipped the way the
+    /// tile is. Empty cells and tiles past the end of the tile set have no flags
+    pub fn tile(&self, x: i32, y: i32) -> (u32, u64) {
+        let index = (x + y * self.width) as usize;
+        if self.empty[index] {
+            return (0, 0);
+        }
+
+        let tile = self.tiles[index];
+        let id = map_tile::id(tile);
+        let mask = *self.tile_set.masks.get(id).unwrap_or(&0);
+        (
+            *self.tile_set.flags.get(id).unwrap_or(&0),
+            tile_ids::flip_mask(
+                mask,
+                tile & map_tile::HFLIP != 0,
+                tile & map_tile::VFLIP != 0,
+            ),
+        )
+    }
+}
+
+/// Stops levels the game can't show from being built, and points out ones that will look
+/// different to how they do in Tiled. Returns whether the level can be exported
+fn check_level_dimensions(
+    filename: &str,
+    level: &TiledLevel,
+    diagnostics: &mut Diagnostics,
+) -> bool {
+    if level.tilewidth != TILE_PIXELS || level.tileheight != TILE_PIXELS {
+        diagnostics.error(
+            filename,
+            format!(
+                "Uses {}x{} tiles but the GBA backgrounds need {TILE_PIXELS}x{TILE_PIXELS}",
+                level.tilewidth, level.tileheight
+            ),
+        );
+        return false;
+    }
+
+    if level.width <= 0 || level.height <= 0 {
+        diagnostics.error(
+            filename,
+            format!(
+                "Is {}x{} tiles, it needs at least one tile",
+                level.width, level.height
+            ),
+        );
+        return false;
+    }
+
+    if level.width > MAX_LEVEL_TILES || level.height > MAX_LEVEL_TILES {
+        diagnostics.error(
+            filename,
+            format!(
+                "Is {}x{} tiles, levels can be at most {MAX_LEVEL_TILES} tiles across",
+                level.width, level.height
+            ),
+        );
+        return false;
+    }
+
+    let mut valid = true;
+    for layer in &level.layers {
+        if let Some(data) = &layer.data {
+            if data.len() != (level.width * level.height) as usize {
+                diagnostics
+                    .error(
+                        filename,
+                        format!(
+                            "Has {} tiles but the level is {}x{}",
+                            data.len(),
+                            level.width,
+                            level.height
+                        ),
+                    )
+                    .layer(&layer.name);
+                valid = false;
+            }
+        }
+    }
+
+    if level.width < SCREEN_TILES.0 || level.height < SCREEN_TILES.1 {
+        diagnostics.warning(
+            filename,
+            format!(
+                "Is {}x{} tiles, smaller than the {}x{} screen so it will be centred",
+                level.width, level.height, SCREEN_TILES.0, SCREEN_TILES.1
+            ),
+        );
+    }
+
+    valid
+}
+
+/// The index of the tile set with `name` in its file name in the map's list of tile sets
+fn get_tileset(
+    filename: &str,
+    level: &TiledLevel,
+    name: &str,
+    diagnostics: &mut Diagnostics,
+) -> Option<usize> {
+    let tileset = level
+        .tilesets
+        .iter()
+        .position(|tileset| tileset.source.contains(name));
+
+    if tileset.is_none() {
+        diagnostics.error(
+            filename,
+            format!("No {name} tile set, expected one with {name:?} in its file name"),
+        );
+    }
+
+    tileset
+}
+
+fn get_layer<'a>(
+    filename: &str,
+    level: &'a TiledLevel,
+    name: Layers,
+    diagnostics: &mut Diagnostics,
+) -> Option<&'a TiledLayer> {
+    let layer = level.layers.iter().find(|layer| layer.name == name);
+
+    if layer.is_none() {
+        diagnostics.error(filename, format!("No {name:?} layer"));
+    }
+
+    layer
+}
+
+/// Spawns are checked with the collision box the game gives what's spawned there, every
+/// pixel of it has to be clear of solid tiles and the sides and top of the level
+fn check_spawn(
+    filename: &str,
+    object: &TiledObject,
+    layers: &[&Layer],
+    diagnostics: &mut Diagnostics,
+) {
+    let (x, y) = (object.x, object.y);
+    let (width, height) = (layers[0].width(), layers[0].height());
+    if x < 0 || y < 0 || x >= width * TILE_PIXELS || y >= height * TILE_PIXELS {
+        diagnostics
+            .error(filename, "Spawns outside the level")
+            .object(object);
+        return;
+    }
+
+    let (left, top, right, bottom) = spawn_box(object);
+    let solid = (top.div_euclid(TILE_PIXELS)..=bottom.div_euclid(TILE_PIXELS)).any(|tile_y| {
+        (left.div_euclid(TILE_PIXELS)..=right.div_euclid(TILE_PIXELS)).any(|tile_x| {
+            // The game treats the sides and top of the level as walls, but below it is a pit
+            if tile_x < 0 || tile_x >= width || tile_y < 0 {
+                return true;
+            }
+            if tile_y >= height {
+                return false;
+            }
+
+            let area = pixel_area(
+                left - tile_x * TILE_PIXELS,
+                right - tile_x * TILE_PIXELS,
+                top - tile_y * TILE_PIXELS,
+                bottom - tile_y * TILE_PIXELS,
+            );
+            layers.iter().any(|layer| {
+                let (flags, mask) = layer.tile(tile_x, tile_y);
+                flags & COLLISION_TILE != 0 && mask & area != 0
+            })
+        })
+    });
+
+    if solid {
+        diagnostics
+            .error(filename, "Spawns inside a solid tile")
+            .object(object);
+    }
+}
+
+/// The pixels `(left, top, right, bottom)` the game's collision box covers for what's spawned
+/// at `object`, inclusive
+fn spawn_box(object: &TiledObject) -> (i32, i32, i32, i32) {
+    let (x, y) = (object.x, object.y);
+    match object.object_type.as_str() {
+        // 28x14 and centred on the spawn
+        "Boar Spawn" => (x - 14, y - 7, x + 13, y + 6),
+        // 16x16 with the spawn on its bottom row, the same as the player
+        _ => (x - 8, y - 15, x + 7, y),
+    }
+}
+
+/// Flood fills the level from the player start through every tile that isn't solid or
+/// deadly. This ignores gravity so it lets through levels that can't really be finished,
+/// but a level it can't find a way through definitely can't be. Only a warning so levels
+/// can be built while they're still being made
+fn check_win_reachable(
+    filename: &str,
+    level: &TiledLevel,
+    player_start: (i32, i32),
+    layers: &[&Layer],
+    diagnostics: &mut Diagnostics,
+) {
+    let flags_at = |x: i32, y: i32| {
+        layers.iter().fold((0, 0), |(flags, mask), layer| {
+            let (tile_flags, tile_mask) = layer.tile(x, y);
+            let solid_mask = if tile_flags & COLLISION_TILE != 0 {
+                tile_mask
+            } else {
+                0
+            };
+            (flags | tile_flags, mask | solid_mask)
+        })
+    };
+
+    let has_win = (0..level.height)
+        .flat_map(|y| (0..level.width).map(move |x| (x, y)))
+        .any(|(x, y)| flags_at(x, y).0 & WIN_TILE != 0);
+    if !has_win {
+        diagnostics.warning(filename, "Has no Win tiles so it can't be finished");
+        return;
+    }
+
+    let start = (
+        player_start.0.clamp(0, level.width * TILE_PIXELS - 1) / TILE_PIXELS,
+        (player_start.1 - 1).clamp(0, level.height * TILE_PIXELS - 1) / TILE_PIXELS,
+    );
+    let mut visited = vec![false; (level.width * level.height) as usize];
+    let mut to_visit = vec![start];
+
+    while let Some((x, y)) = to_visit.pop() {
+        if x < 0 || y < 0 || x >= level.width || y >= level.height {
+            continue;
+        }
+
+        let index = (x + y * level.width) as usize;
+        if visited[index] {
+            continue;
+        }
+        visited[index] = true;
+
+        let (flags, solid_mask) = flags_at(x, y);
+        if flags & WIN_TILE != 0 {
+            return;
+        }
+        // Win tiles are often solid, so they only need to be touched
+        if (x, y) != start && (solid_mask == FULL_TILE_MASK || flags & KILL_TILE != 0) {
+            continue;
+        }
+
+        to_visit.extend([(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]);
+    }
+
+    diagnostics
+        .warning(filename, "No Win tile can be reached from the Player Start")
+        .layer(&Layers::Objects)
+        .at(player_start.0, player_start.1);
+}
+
+/// Reads the custom properties set on the map itself. The name falls back to the level's
+/// file name and the background colour to the map's own background colour
+fn get_level_metadata(
+    level_file: &str,
+    filename: &str,
+    level: &TiledLevel,
+    diagnostics: &mut Diagnostics,
+) -> LevelMetadata {
+    let property = |name: &str| {
+        level
+            .properties
+            .iter()
+            .flatten()
+            .find(|property| property.name == name)
+            .map(|property| &property.value)
+    };
+    let string_property =
+        |name: &str| property(name).and_then(|value| value.as_str().map(|value| value.to_string()));
+
+    LevelMetadata {
+        name: string_property("Name").unwrap_or_else(|| level_file.replace('_', " ")),
+        subtitle: string_property("Subtitle"),
+        intro: string_property("Intro"),
+        par_time: property("Par Time")
+            .and_then(|par_time| par_time.as_f64())
+            .map(|seconds| (seconds * FRAMES_PER_SECOND) as i32),
+        music_id: property("Music")
+            .and_then(|music_id| music_id.as_u64())
+            .map(|music_id| music_id as u32),
+        background_colour: property("Background Colour")
+            .and_then(|colour| colour.as_str())
+            .or(level.backgroundcolor.as_deref())
+            .and_then(|colour| get_rgb_hex(filename, "background colour", colour, diagnostics))
+            .unwrap_or_else(|| DEFAULT_BACKGROUND_COLOUR.to_string()),
+    }
+}
+
+/// Converts a Tiled `#rrggbb` or `#aarrggbb` colour to the `rrggbb` agb expects, `name` is
+/// what the colour is for in the error if it isn't one
+pub(crate) fn get_rgb_hex(
+    filename: &str,
+    name: &str,
+    colour: &str,
+    diagnostics: &mut Diagnostics,
+) -> Option<String> {
+    let hex = colour.strip_prefix('#').unwrap_or(colour);
+    // Checked before slicing so the alpha can't be cut off in the middle of a character
+    if !matches!(hex.len(), 6 | 8) || !hex.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+        diagnostics.error(filename, format!("Invalid {name} {colour:?}"));
+        return None;
+    }
+    Some(hex[hex.len() - 6..].to_lowercase())
+}
@@ -0,0 +1,24 @@
+//! Reads the game's levels from Tiled and writes them out as Rust for the game to include.
+//!
+//! The game's build script uses this, but it is a normal host crate so it can be tested with
+//! `cargo test-tiled-export --target <your host target>`
+
+mod chunks;
+mod collision;
+mod diagnostics;
+mod export;
+mod level;
+pub mod tile_ids;
+mod tile_set;
+mod tiled;
+mod tmx;
+
+pub use chunks::{ChunkedLayer, CHUNK_SIZE, CHUNK_TILES};
+pub use collision::{CollisionGrid, LayerCollision};
+pub use diagnostics::{Diagnostic, Diagnostics, Severity};
+pub use export::{tile_set_images, write_assets, write_level, RomSize, RomSizes};
+pub use level::{load_level, map_path, Layer, Level, LevelMetadata};
+pub use simulation::{map_tile, tilemap};
+pub use tile_set::TileSet;
+
+pub const TILE_PIXELS: i32 = 8;
@@ -0,0 +1,169 @@
+//! Turns the global tile ids Tiled stores in tile layers into an id in one of the map's tile
+//! sets along with how the tile is flipped
+
+/// Tiled keeps how a tile is flipped in the top four bits of its global id
+pub const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+pub const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+/// Flips the tile across its top left to bottom right diagonal, Tiled uses it to rotate tiles
+pub const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+/// Only used by hexagonal maps
+pub const ROTATED_HEXAGONAL_120: u32 = 0x1000_0000;
+pub const FLIP_BITS: u32 =
+    FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL_120;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MapTile {
+    /// Index of the tile set in the map's list of tile sets
+    pub tileset: usize,
+    /// Id of the tile in its tile set
+    pub id: u32,
+    pub hflip: bool,
+    pub vflip: bool,
+    pub diagonal_flip: bool,
+}
+
+/// `first_ids` are the `firstgid`s of the map's tile sets. Empty tiles and ids before the first
+/// tile set give `None`
+pub fn decode_gid(gid: u32, first_ids: &[u32]) -> Option<MapTile> {
+    let global_id = gid & !FLIP_BITS;
+    if global_id == 0 {
+        return None;
+    }
+
+    // A tile belongs to the tile set with the highest first id that isn't past it
+    let (tileset, first_id) = first_ids
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|&(_, first_id)| first_id <= global_id)
+        .max_by_key(|&(_, first_id)| first_id)?;
+
+    Some(MapTile {
+        tileset,
+        id: global_id - first_id,
+        hflip: gid & FLIPPED_HORIZONTALLY != 0,
+        vflip: gid & FLIPPED_VERTICALLY != 0,
+        diagonal_flip: gid & FLIPPED_DIAGONALLY != 0,
+    })
+}
+
+/// Flips a hit mask with bit `y * 8 + x` set for each covered pixel the same way as its tile
+pub const fn flip_mask(mask: u64, hflip: bool, vflip: bool) -> u64 {
+    // Each row is a byte, so reversing every bit reverses the rows and the pixels in them
+    let mask = if hflip {
+        mask.reverse_bits().swap_bytes()
+    } else {
+        mask
+    };
+
+    if vflip {
+        mask.swap_bytes()
+    } else {
+        mask
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile(tileset: usize, id: u32) -> MapTile {
+        MapTile {
+            tileset,
+            id,
+            hflip: false,
+            vflip: false,
+            diagonal_flip: false,
+        }
+    }
+
+    #[test]
+    fn empty_tile_has_no_tile() {
+        assert_eq!(decode_gid(0, &[1, 2501]), None);
+    }
+
+    #[test]
+    fn empty_tile_with_flip_bits_has_no_tile() {
+        assert_eq!(decode_gid(FLIPPED_HORIZONTALLY, &[1]), None);
+    }
+
+    #[test]
+    fn first_tile_set_starts_at_zero() {
+        assert_eq!(decode_gid(1, &[1, 2501]), Some(tile(0, 0)));
+        assert_eq!(decode_gid(2500, &[1, 2501]), Some(tile(0, 2499)));
+    }
+
+    #[test]
+    fn later_tile_sets_count_from_their_first_id() {
+        assert_eq!(decode_gid(2501, &[1, 2501]), Some(tile(1, 0)));
+        assert_eq!(decode_gid(3379, &[1, 2501]), Some(tile(1, 878)));
+    }
+
+    #[test]
+    fn any_number_of_tile_sets() {
+        let first_ids = [1, 11, 21, 31];
+        for (tileset, &first_id) in first_ids.iter().enumerate() {
+            for id in 0..10 {
+                assert_eq!(
+                    decode_gid(first_id + id, &first_ids),
+                    Some(tile(tileset, id))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tile_sets_in_any_order() {
+        assert_eq!(decode_gid(15, &[11, 1]), Some(tile(0, 4)));
+        assert_eq!(decode_gid(5, &[11, 1]), Some(tile(1, 4)));
+    }
+
+    #[test]
+    fn ids_before_the_first_tile_set_have_no_tile() {
+        assert_eq!(decode_gid(3, &[5]), None);
+    }
+
+    #[test]
+    fn flip_bits_are_decoded() {
+        let gid = 2502 | FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY;
+        assert_eq!(
+            decode_gid(gid, &[1, 2501]),
+            Some(MapTile {
+                hflip: true,
+                vflip: true,
+                ..tile(1, 1)
+            })
+        );
+
+        assert_eq!(
+            decode_gid(7 | FLIPPED_DIAGONALLY, &[1]),
+            Some(MapTile {
+                diagonal_flip: true,
+                ..tile(0, 6)
+            })
+        );
+    }
+
+    #[test]
+    fn hexagonal_rotation_is_ignored() {
+        assert_eq!(
+            decode_gid(7 | ROTATED_HEXAGONAL_120, &[1]),
+            Some(tile(0, 6))
+        );
+    }
+
+    #[test]
+    fn flipping_masks() {
+        // The top left pixel
+        let mask = 1;
+        assert_eq!(flip_mask(mask, false, false), mask);
+        assert_eq!(flip_mask(mask, true, false), 1 << 7);
+        assert_eq!(flip_mask(mask, false, true), 1 << 56);
+        assert_eq!(flip_mask(mask, true, true), 1 << 63);
+
+        // The bottom row
+        let floor = 0xff << 56;
+        assert_eq!(flip_mask(floor, true, false), floor);
+        assert_eq!(flip_mask(floor, false, true), 0xff);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::diagnostics::Diagnostics;
+use crate::level::get_rgb_hex;
+use crate::tiled::{read_tile_set, TiledCollisionShape, TiledProperty};
+use crate::tilemap::*;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Every pixel of an 8x8 tile set, used for tiles without their own collision shapes
+pub const FULL_TILE_MASK: u64 = u64::MAX;
+/// The colour the tile set images use for transparent pixels unless the tile set has a
+/// `Transparent Colour` property
+pub const DEFAULT_TRANSPARENT_COLOUR: &str = "2ce8f4";
+
+pub struct TileSet {
+    /// Flags for every tile in the tile set
+    pub flags: Vec<u32>,
+    /// Hit masks for every tile in the tile set, bit `y * 8 + x` is set when the pixel is solid
+    pub masks: Vec<u64>,
+    /// As `rrggbb` hex, pixels of this colour in the tile set's image are transparent
+    pub transparent_colour: String,
+}
+
+impl TileSet {
+    pub(crate) fn load(filename: &Path, diagnostics: &mut Diagnostics) -> Option<Self> {
+        let tilemap = read_tile_set(filename, diagnostics)?;
+        let transparent_colour = tilemap
+            .properties
+            .iter()
+            .flatten()
+            .find(|property| property.name == "Transparent Colour")
+            .and_then(|property| property.value.as_str())
+            .and_then(|colour| {
+                get_rgb_hex(
+                    &filename.display().to_string(),
+                    "transparent colour",
+                    colour,
+                    diagnostics,
+                )
+            })
+            .unwrap_or_else(|| DEFAULT_TRANSPARENT_COLOUR.to_string());
+        let tiles = tilemap.tiles.unwrap_or_default();
+
+        let tile_data: HashMap<_, _> = tiles
+            .iter()
+            .map(|tile| {
+                let property_flags = tile
+                    .properties
+                    .iter()
+                    .flatten()
+                    .filter(|property| property.value == serde_json::Value::Bool(true))
+                    .fold(0, |flags, property| flags | get_tile_flag(&property.name));
+                let direction_flags = tile
+                    .properties
+                    .iter()
+                    .flatten()
+                    .fold(0, |flags, property| flags | get_direction_flag(property));
+
+                (
+                    tile.id,
+                    get_tile_flag(&tile.tile_type) | property_flags | direction_flags,
+                )
+            })
+            .collect();
+
+        let file = filename.display().to_string();
+        let tile_masks: HashMap<_, _> = tiles
+            .iter()
+            .filter_map(|tile| {
+                tile.objectgroup.as_ref().map(|group| {
+                    let mask = get_tile_mask(&file, tile.id, &group.objects, diagnostics);
+                    (tile.id, mask)
+                })
+            })
+            .collect();
+
+        Some(TileSet {
+            flags: (0..tilemap.tilecount)
+                .map(|id| *tile_data.get(&id).unwrap_or(&0))
+                .collect(),
+            masks: (0..tilemap.tilecount)
+                .map(|id| *tile_masks.get(&id).unwrap_or(&FULL_TILE_MASK))
+                .collect(),
+            transparent_colour,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.flags.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.flags.is_empty()
+    }
+}
+
+/// Tile behaviours can come from the tile's type or a bool custom property of the same name,
+/// so a tile can be given several at once
+fn get_tile_flag(name: &str) -> u32 {
+    match name {
+        "Collision" => COLLISION_TILE,
+        "Kill" => KILL_TILE,
+        "Win" => WIN_TILE,
+        "Ladder" => LADDER_TILE,
+        "Water" => WATER_TILE,
+        "Platform" => PLATFORM_TILE,
+        "Ice" => ICE_TILE,
+        "Conveyor" => CONVEYOR_TILE,
+        _ => 0,
+    }
+}
+
+/// Conveyors move right unless given a `Direction` property of `Left` or a negative number
+fn get_direction_flag(property: &TiledProperty) -> u32 {
+    if property.name != "Direction" {
+        return 0;
+    }
+
+    let is_left = match &property.value {
+        serde_json::Value::String(direction) => direction == "Left",
+        serde_json::Value::Number(direction) => direction.as_f64().is_some_and(|d| d < 0.0),
+        _ => false,
+    };
+
+    if is_left {
+        CONVEYOR_LEFT_TILE
+    } else {
+        0
+    }
+}
+
+/// Turns the collision rectangles Tiled stores on a tile into a 64 bit mask,
+/// one bit per pixel with bit `y * 8 + x` set when the pixel is covered. Only rectangles are
+/// supported, other shapes are reported
+fn get_tile_mask(
+    filename: &str,
+    tile_id: i32,
+    objects: &[TiledCollisionShape],
+    diagnostics: &mut Diagnostics,
+) -> u64 {
+    let mut mask = 0;
+    for shape in objects {
+        if shape.width <= 0.0 || shape.height <= 0.0 {
+            // Points and polygons have no size
+            diagnostics.warning(
+                filename,
+                format!(
+                    "Tile {tile_id} has a collision shape that isn't a rectangle, it's ignored"
+                ),
+            );
+            continue;
+        }
+        if shape.ellipse {
+            diagnostics.warning(
+                filename,
+                format!("Tile {tile_id} has an ellipse collision shape, it's used as a rectangle"),
+            );
+        }
+
+        let left = (shape.x.floor() as i32).clamp(0, 8);
+        let right = ((shape.x + shape.width).ceil() as i32).clamp(0, 8);
+        let top = (shape.y.floor() as i32).clamp(0, 8);
+        let bottom = ((shape.y + shape.height).ceil() as i32).clamp(0, 8);
+
+        for y in top..bottom {
+            for x in left..right {
+                mask |= 1 << (y * 8 + x);
+            }
+        }
+    }
+    mask
+}
@@ -0,0 +1,148 @@
+//! The parts of Tiled's formats the exporter reads
+
+use crate::diagnostics::Diagnostics;
+use crate::tmx;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Reads a `.tmx` map, or a map exported as JSON
+pub(crate) fn read_map(filename: &Path, diagnostics: &mut Diagnostics) -> Option<TiledLevel> {
+    if is_json(filename) {
+        read_json(filename, diagnostics)
+    } else {
+        tmx::read_map(filename, diagnostics)
+    }
+}
+
+/// Reads a `.tsx` tile set, or a tile set exported as JSON
+pub(crate) fn read_tile_set(
+    filename: &Path,
+    diagnostics: &mut Diagnostics,
+) -> Option<TiledTilemap> {
+    if is_json(filename) {
+        read_json(filename, diagnostics)
+    } else {
+        tmx::read_tile_set(filename, diagnostics)
+    }
+}
+
+fn is_json(filename: &Path) -> bool {
+    filename
+        .extension()
+        .is_some_and(|extension| extension == "json")
+}
+
+fn read_json<T: DeserializeOwned>(filename: &Path, diagnostics: &mut Diagnostics) -> Option<T> {
+    let name = filename.display().to_string();
+    let file = match File::open(filename) {
+        Ok(file) => file,
+        Err(error) => {
+            diagnostics.error(&name, format!("Failed to open: {error}"));
+            return None;
+        }
+    };
+
+    match serde_json::from_reader(BufReader::new(file)) {
+        Ok(value) => Some(value),
+        Err(error) => {
+            diagnostics.error(&name, format!("Failed to parse: {error}"));
+            None
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TiledLevel {
+    pub layers: Vec<TiledLayer>,
+    pub width: i32,
+    pub height: i32,
+    pub tilewidth: i32,
+    pub tileheight: i32,
+    pub tilesets: Vec<TileSetReference>,
+    pub properties: Option<Vec<TiledProperty>>,
+    pub backgroundcolor: Option<String>,
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+pub(crate) enum Layers {
+    World,
+    WorldObjects,
+    Objects,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TiledLayer {
+    pub name: Layers,
+    pub data: Option<Vec<u32>>,
+    pub objects: Option<Vec<TiledObject>>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TiledObject {
+    pub id: i32,
+    #[serde(rename = "type")]
+    pub object_type: String,
+    #[serde(deserialize_with = "float_to_i32")]
+    pub x: i32,
+    #[serde(deserialize_with = "float_to_i32")]
+    pub y: i32,
+    #[serde(default, deserialize_with = "float_to_i32")]
+    pub width: i32,
+    #[serde(default, deserialize_with = "float_to_i32")]
+    pub height: i32,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TiledTilemap {
+    pub tiles: Option<Vec<TiledTile>>,
+    pub tilecount: i32,
+    pub properties: Option<Vec<TiledProperty>>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TiledTile {
+    pub id: i32,
+    #[serde(rename = "type", default)]
+    pub tile_type: String,
+    pub objectgroup: Option<TiledObjectGroup>,
+    pub properties: Option<Vec<TiledProperty>>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TiledProperty {
+    pub name: String,
+    pub value: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TiledObjectGroup {
+    pub objects: Vec<TiledCollisionShape>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TiledCollisionShape {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    #[serde(default)]
+    pub ellipse: bool,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TileSetReference {
+    pub firstgid: u32,
+    /// Relative to the map
+    pub source: String,
+}
+
+fn float_to_i32<'de, D>(deserializer: D) -> Result<i32, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let f = f32::deserialize(deserializer)?;
+    Ok(f as i32)
+}
@@ -0,0 +1,290 @@
+//! Reads Tiled's own `.tmx` maps and `.tsx` tile sets into the same structures as its JSON
+//! exports, so levels don't need exporting every time they're saved
+
+use crate::diagnostics::Diagnostics;
+use crate::tiled::{
+    Layers, TileSetReference, TiledCollisionShape, TiledLayer, TiledLevel, TiledObject,
+    TiledObjectGroup, TiledProperty, TiledTile, TiledTilemap,
+};
+use base64::Engine;
+use roxmltree::{Document, Node};
+use std::path::Path;
+use std::str::FromStr;
+
+pub(crate) fn read_map(filename: &Path, diagnostics: &mut Diagnostics) -> Option<TiledLevel> {
+    read_xml(filename, diagnostics, parse_map)
+}
+
+pub(crate) fn read_tile_set(
+    filename: &Path,
+    diagnostics: &mut Diagnostics,
+) -> Option<TiledTilemap> {
+    read_xml(filename, diagnostics, parse_tile_set)
+}
+
+fn read_xml<T>(
+    filename: &Path,
+    diagnostics: &mut Diagnostics,
+    parse: impl Fn(Node) -> Result<T, String>,
+) -> Option<T> {
+    let name = filename.display().to_string();
+    let text = match std::fs::read_to_string(filename) {
+        Ok(text) => text,
+        Err(error) => {
+            diagnostics.error(&name, format!("Failed to open: {error}"));
+            return None;
+        }
+    };
+
+    let parsed = Document::parse(&text)
+        .map_err(|error| error.to_string())
+        .and_then(|document| parse(document.root_element()));
+
+    match parsed {
+        Ok(value) => Some(value),
+        Err(error) => {
+            diagnostics.error(&name, format!("Failed to parse: {error}"));
+            None
+        }
+    }
+}
+
+fn parse_map(map: Node) -> Result<TiledLevel, String> {
+    expect_tag(map, "map")?;
+    if attribute(map, "infinite").unwrap_or(0) != 0 {
+        return Err("Infinite maps aren't supported".to_string());
+    }
+
+    let mut layers = vec![];
+    let mut tilesets = vec![];
+    let mut properties = None;
+
+    for child in elements(map) {
+        match child.tag_name().name() {
+            "tileset" => tilesets.push(TileSetReference {
+                firstgid: attribute(child, "firstgid")?,
+                source: attribute(child, "source")?,
+            }),
+            "properties" => properties = Some(parse_properties(child)?),
+            "layer" => layers.push(TiledLayer {
+                name: parse_layer_name(child)?,
+                data: Some(parse_layer_data(child)?),
+                objects: None,
+            }),
+            "objectgroup" => layers.push(TiledLayer {
+                name: parse_layer_name(child)?,
+                data: None,
+                objects: Some(
+                    elements(child)
+                        .filter(|node| node.has_tag_name("object"))
+                        .map(parse_object)
+                        .collect::<Result<_, _>>()?,
+                ),
+            }),
+            _ => {}
+        }
+    }
+
+    Ok(TiledLevel {
+        layers,
+        width: attribute(map, "width")?,
+        height: attribute(map, "height")?,
+        tilewidth: attribute(map, "tilewidth")?,
+        tileheight: attribute(map, "tileheight")?,
+        tilesets,
+        properties,
+        backgroundcolor: map
+            .attribute("backgroundcolor")
+            .map(|colour| colour.to_string()),
+    })
+}
+
+fn parse_tile_set(tileset: Node) -> Result<TiledTilemap, String> {
+    expect_tag(tileset, "tileset")?;
+
+    let tiles = elements(tileset)
+        .filter(|node| node.has_tag_name("tile"))
+        .map(|tile| {
+            let mut properties = None;
+            let mut objectgroup = None;
+            for child in elements(tile) {
+                match child.tag_name().name() {
+                    "properties" => properties = Some(parse_properties(child)?),
+                    "objectgroup" => {
+                        objectgroup = Some(TiledObjectGroup {
+                            objects: elements(child)
+                                .filter(|node| node.has_tag_name("object"))
+                                .map(|shape| {
+                                    Ok(TiledCollisionShape {
+                                        x: attribute(shape, "x")?,
+                                        y: attribute(shape, "y")?,
+                                        width: attribute(shape, "width").unwrap_or(0.0),
+                                        height: attribute(shape, "height").unwrap_or(0.0),
+                                        ellipse: elements(shape)
+                                            .any(|node| node.has_tag_name("ellipse")),
+                                    })
+                                })
+                                .collect::<Result<_, String>>()?,
+                        })
+                    }
+                    _ => {}
+                }
+            }
+
+            Ok(TiledTile {
+                id: attribute(tile, "id")?,
+                tile_type: class(tile),
+                objectgroup,
+                properties,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let properties = elements(tileset)
+        .find(|node| node.has_tag_name("properties"))
+        .map(parse_properties)
+        .transpose()?;
+
+    Ok(TiledTilemap {
+        tiles: Some(tiles),
+        tilecount: attribute(tileset, "tilecount")?,
+        properties,
+    })
+}
+
+fn parse_layer_name(layer: Node) -> Result<Layers, String> {
+    let name = layer.attribute("name").unwrap_or_default();
+    match name {
+        "World" => Ok(Layers::World),
+        "WorldObjects" => Ok(Layers::WorldObjects),
+        "Objects" => Ok(Layers::Objects),
+        _ => Err(format!("Unknown layer {name:?}")),
+    }
+}
+
+/// Tile layers can be stored as CSV, base64 with or without zlib compression, or as a
+/// `<tile>` element for every tile
+fn parse_layer_data(layer: Node) -> Result<Vec<u32>, String> {
+    let data = elements(layer)
+        .find(|node| node.has_tag_name("data"))
+        .ok_or("Tile layer has no data")?;
+    let text = data.text().unwrap_or_default().trim();
+
+    match (data.attribute("encoding"), data.attribute("compression")) {
+        (Some("csv"), _) => text
+            .split(',')
+            .map(|gid| {
+                gid.trim()
+                    .parse()
+                    .map_err(|_| format!("Invalid tile id {:?}", gid.trim()))
+            })
+            .collect(),
+        (Some("base64"), compression) => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(text)
+                .map_err(|error| format!("Invalid base64 tile data: {error}"))?;
+
+            let bytes = match compression {
+                None => bytes,
+                Some("zlib") => miniz_oxide::inflate::decompress_to_vec_zlib(&bytes)
+                    .map_err(|error| format!("Invalid zlib tile data: {error:?}"))?,
+                Some(compression) => {
+                    return Err(format!(
+                        "{compression} compressed layers aren't supported, use zlib"
+                    ))
+                }
+            };
+
+            if bytes.len() % 4 != 0 {
+                return Err("Tile data isn't a whole number of tile ids".to_string());
+            }
+
+            Ok((0..bytes.len())
+                .step_by(4)
+                .map(|at| {
+                    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+                })
+                .collect())
+        }
+        (None, _) => elements(data)
+            .filter(|node| node.has_tag_name("tile"))
+            .map(|tile| Ok(attribute(tile, "gid").unwrap_or(0)))
+            .collect(),
+        (Some(encoding), _) => Err(format!("Unknown layer encoding {encoding:?}")),
+    }
+}
+
+fn parse_object(object: Node) -> Result<TiledObject, String> {
+    Ok(TiledObject {
+        id: attribute(object, "id")?,
+        object_type: class(object),
+        x: attribute::<f32>(object, "x")? as i32,
+        y: attribute::<f32>(object, "y")? as i32,
+        width: attribute::<f32>(object, "width").unwrap_or(0.0) as i32,
+        height: attribute::<f32>(object, "height").unwrap_or(0.0) as i32,
+    })
+}
+
+/// Properties are typed in the XML, so they're turned into the JSON values they would be
+/// exported as
+fn parse_properties(properties: Node) -> Result<Vec<TiledProperty>, String> {
+    elements(properties)
+        .filter(|node| node.has_tag_name("property"))
+        .map(|property| {
+            // Multi-line strings are stored as the element's text instead of an attribute
+            let value = property
+                .attribute("value")
+                .or(property.text())
+                .unwrap_or_default();
+
+            let value = match property.attribute("type").unwrap_or("string") {
+                "bool" => serde_json::Value::Bool(value == "true"),
+                "int" | "float" | "object" => serde_json::from_str(value)
+                    .map_err(|_| format!("Invalid number {value:?} in property"))?,
+                _ => serde_json::Value::String(value.to_string()),
+            };
+
+            Ok(TiledProperty {
+                name: attribute(property, "name")?,
+                value,
+            })
+        })
+        .collect()
+}
+
+/// Objects and tiles were given classes instead of types in Tiled 1.9, both are accepted
+fn class(node: Node) -> String {
+    node.attribute("type")
+        .or(node.attribute("class"))
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn elements<'a, 'input>(node: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(|node| node.is_element())
+}
+
+fn expect_tag(node: Node, name: &str) -> Result<(), String> {
+    if node.has_tag_name(name) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Expected a <{name}> but found a <{}>",
+            node.tag_name().name()
+        ))
+    }
+}
+
+fn attribute<T: FromStr>(node: Node, name: &str) -> Result<T, String> {
+    let value = node.attribute(name).ok_or_else(|| {
+        format!(
+            "<{}> on line {} has no {name}",
+            node.tag_name().name(),
+            node.document().text_pos_at(node.range().start).row
+        )
+    })?;
+
+    value
+        .parse()
+        .map_err(|_| format!("Invalid {name} {value:?} on <{}>", node.tag_name().name()))
+}
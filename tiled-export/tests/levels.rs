@@ -0,0 +1,502 @@
+use std::path::{Path, PathBuf};
+use tiled_export::{
+    load_level, map_tile, tilemap, write_assets, write_level, ChunkedLayer, CollisionGrid,
+    Diagnostics, Level, CHUNK_SIZE,
+};
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../map/tests")
+}
+
+fn load(name: &str) -> (Option<Level>, Diagnostics) {
+    let mut diagnostics = Diagnostics::default();
+    let level = load_level(&fixtures(), name, &mut diagnostics);
+    (level, diagnostics)
+}
+
+fn messages<'a>(diagnostics: impl Iterator<Item = &'a tiled_export::Diagnostic>) -> Vec<String> {
+    diagnostics
+        .map(|diagnostic| diagnostic.message.clone())
+        .collect()
+}
+
+#[test]
+fn loads_a_valid_level() {
+    let (level, diagnostics) = load("valid");
+    let level = level.expect("valid level should load");
+
+    assert_eq!(diagnostics.errors().count(), 0);
+    assert_eq!((level.width, level.height), (4, 3));
+    assert_eq!(level.player_start, (8, 15));
+    assert_eq!(level.boars, vec![(16, 9)]);
+    assert_eq!(level.enemy_stops, vec![(20, 16)]);
+    assert_eq!(level.camera_bounds, vec![(0, 0, 32, 24)]);
+    assert!(level.snails.is_empty() && level.slimes.is_empty());
+
+    assert_eq!(
+        level.background.tiles,
+        vec![0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 1, 1]
+    );
+    assert_eq!(level.foreground.tiles, vec![0; 12]);
+    assert_eq!(level.background.tile(3, 1).0, tilemap::WIN_TILE);
+    assert_eq!(level.background.tile(0, 2).0, tilemap::COLLISION_TILE);
+}
+
+#[test]
+fn reads_metadata_from_map_properties() {
+    let level = load("valid").0.unwrap();
+
+    assert_eq!(level.metadata.name, "Test Level");
+    assert_eq!(level.metadata.par_time, Some(90 * 60));
+    assert_eq!(level.metadata.subtitle, None);
+    assert_eq!(level.metadata.music_id, Some(3));
+    assert_eq!(level.metadata.background_colour, "112233");
+}
+
+#[test]
+fn colours_that_arent_hex_are_errors() {
+    let (_, diagnostics) = load("bad_colour");
+
+    assert_eq!(
+        messages(diagnostics.errors()),
+        vec!["Invalid background colour \"#ffé2233\"".to_string()]
+    );
+}
+
+#[test]
+fn tile_sets_keep_their_own_transparent_colour() {
+    let level = load("valid").0.unwrap();
+    assert_eq!(level.metadata.background_colour, "112233");
+    assert_eq!(level.background.tile_set.transparent_colour, "2ce8f4");
+    assert_eq!(level.foreground.tile_set.transparent_colour, "2ce8f4");
+
+    let (level, diagnostics) = load("transparent_colour");
+    assert_eq!(
+        level.unwrap().background.tile_set.transparent_colour,
+        "ff00ff"
+    );
+    assert_eq!(
+        messages(diagnostics.errors()),
+        vec![
+            "The background tile set's transparent colour is ff00ff but the foreground's is \
+             2ce8f4, they need to be the same"
+        ]
+    );
+}
+
+#[test]
+fn collision_shapes_other_than_rectangles_are_warnings() {
+    let (level, diagnostics) = load("ellipse_collision");
+    let level = level.unwrap();
+    assert_eq!(diagnostics.errors().count(), 0);
+    // Beside the warning about the test level being smaller than the screen
+    let tile_warnings: Vec<_> = messages(diagnostics.warnings())
+        .into_iter()
+        .filter(|message| message.starts_with("Tile"))
+        .collect();
+    assert_eq!(
+        tile_warnings,
+        vec![
+            "Tile 3 has a collision shape that isn't a rectangle, it's ignored".to_string(),
+            "Tile 4 has an ellipse collision shape, it's used as a rectangle".to_string(),
+        ]
+    );
+
+    // The ellipse still covers the bottom half of its tile
+    assert_eq!(level.background.tile_set.masks[4], 0xffff_ffff_0000_0000);
+    assert_eq!(level.background.tile_set.masks[3], 0);
+}
+
+#[test]
+fn name_defaults_to_the_file_name() {
+    let level = load("unreachable_win").0.unwrap();
+
+    assert_eq!(level.metadata.name, "unreachable win");
+    assert_eq!(level.metadata.background_colour, "2ce8f4");
+}
+
+#[test]
+fn small_levels_are_a_warning() {
+    let (_, diagnostics) = load("valid");
+
+    assert_eq!(
+        messages(diagnostics.warnings()),
+        vec!["Is 4x3 tiles, smaller than the 30x20 screen so it will be centred"]
+    );
+}
+
+#[test]
+fn missing_player_start_is_an_error() {
+    let (level, diagnostics) = load("missing_start");
+
+    assert!(level.is_none());
+    let errors = diagnostics.errors().collect::<Vec<_>>();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].message,
+        "Need a Player Start object for the player"
+    );
+    assert_eq!(errors[0].layer.as_deref(), Some("Objects"));
+}
+
+#[test]
+fn unknown_objects_are_errors_with_their_id_and_position() {
+    let (_, diagnostics) = load("unknown_object");
+
+    let errors = diagnostics.errors().collect::<Vec<_>>();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "Unknown object type \"Treasure\"");
+    assert_eq!(errors[0].object_id, Some(5));
+    assert_eq!(errors[0].position, Some((12, 8)));
+    assert!(errors[0].file.ends_with("unknown_object_map.json"));
+}
+
+#[test]
+fn spawns_inside_solid_tiles_are_errors() {
+    let (_, diagnostics) = load("spawn_in_solid");
+
+    let errors = diagnostics.errors().collect::<Vec<_>>();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "Spawns inside a solid tile");
+    assert_eq!(errors[0].object_id, Some(2));
+}
+
+#[test]
+fn diagnostics_leave_the_severity_to_whoever_reports_them() {
+    let (_, diagnostics) = load("spawn_in_solid");
+
+    let error = diagnostics.errors().next().unwrap().to_string();
+    assert!(error.ends_with(
+        "spawn_in_solid_map.json, layer Objects, object 2 at (12, 20): Spawns inside a solid tile"
+    ));
+    assert!(!error.contains("error"));
+}
+
+#[test]
+fn spawns_partly_inside_solid_tiles_are_errors() {
+    // The start point itself is clear but the player's box overlaps the wall beside it
+    let (_, diagnostics) = load("spawn_beside_wall");
+
+    let errors = diagnostics.errors().collect::<Vec<_>>();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "Spawns inside a solid tile");
+    assert_eq!(errors[0].object_id, Some(1));
+}
+
+#[test]
+fn unreachable_win_is_a_warning() {
+    let (level, diagnostics) = load("unreachable_win");
+
+    assert!(level.is_some());
+    assert_eq!(diagnostics.errors().count(), 0);
+    assert!(messages(diagnostics.warnings())
+        .contains(&"No Win tile can be reached from the Player Start".to_string()));
+}
+
+#[test]
+fn tile_ids_outside_the_layers_tile_set_are_errors() {
+    let (_, diagnostics) = load("bad_tile_id");
+
+    let errors = diagnostics.errors().collect::<Vec<_>>();
+    assert_eq!(errors.len(), 2);
+
+    assert_eq!(errors[0].layer.as_deref(), Some("World"));
+    assert_eq!(errors[0].tile, Some((0, 0)));
+    assert!(errors[0].message.contains("this layer can only use"));
+
+    assert_eq!(errors[1].layer.as_deref(), Some("WorldObjects"));
+    assert_eq!(errors[1].tile, Some((1, 0)));
+    assert!(errors[1].message.contains("past the end"));
+}
+
+#[test]
+fn flipped_tiles_keep_their_flips_and_flip_their_masks() {
+    let (level, diagnostics) = load("flipped_tiles");
+    let level = level.unwrap();
+
+    assert_eq!(diagnostics.errors().count(), 0);
+    assert_eq!(
+        level.background.tiles[4],
+        4 | map_tile::HFLIP | map_tile::VFLIP
+    );
+
+    // The tile is solid on its bottom half, so upside down only its top half is
+    let (flags, mask) = level.background.tile(0, 1);
+    assert_eq!(flags, tilemap::COLLISION_TILE);
+    assert_eq!(mask, 0x0000_0000_ffff_ffff);
+}
+
+#[test]
+fn missing_levels_are_errors() {
+    let (level, diagnostics) = load("does_not_exist");
+
+    assert!(level.is_none());
+    let errors = messages(diagnostics.errors());
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("Failed to open"));
+}
+
+#[test]
+fn game_levels_load_without_errors() {
+    let map_directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("../map");
+    for level in ["level_1", "level_2"] {
+        let mut diagnostics = Diagnostics::default();
+        assert!(load_level(&map_directory, level, &mut diagnostics).is_some());
+        assert_eq!(messages(diagnostics.errors()), Vec::<String>::new());
+    }
+}
+
+#[test]
+fn writes_the_level_for_the_game() {
+    let level = load("valid").0.unwrap();
+    let out_dir = std::env::temp_dir().join("tiled-export-test");
+    std::fs::create_dir_all(&out_dir).unwrap();
+
+    write_level(&out_dir, &level).unwrap();
+
+    let output = std::fs::read_to_string(out_dir.join("valid.rs")).unwrap();
+    assert!(output.contains("const WIDTH: u32 = 4;"));
+    assert!(output.contains(
+        "const BACKGROUND: ChunkedLayer = ChunkedLayer { width: 1, chunk_map: &[0], \
+         chunks: &[[0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 1, 1, 0, 0, 0, 0]] };"
+    ));
+    assert!(output.contains(
+        "const COLLISION: ChunkedLayer = ChunkedLayer { width: 1, chunk_map: &[0], \
+         chunks: &[[0, 0, 0, 0, 0, 0, 0, 1, 2, 2, 2, 2, 0, 0, 0, 0]] };"
+    ));
+    assert!(output.contains("const START_POS: (i32, i32) = (8, 15);"));
+    assert!(output.contains("const NAME: &str = \"Test Level\";"));
+    assert!(output.contains("const PAR_TIME: Option<i32> = Some(5400);"));
+    assert!(output.contains("const MUSIC_ID: Option<u32> = Some(3);"));
+    assert!(output.contains("const BACKGROUND_COLOUR: u16 = 0x1882;"));
+    assert!(output.contains("use crate::level::map_tiles::assets::valid as graphics;"));
+}
+
+#[test]
+fn rom_sizes_count_tiles_after_removing_duplicates() {
+    let level = load("valid").0.unwrap();
+    let out_dir = std::env::temp_dir().join("tiled-export-assets-test");
+    std::fs::create_dir_all(&out_dir).unwrap();
+    let graphics_directory = fixtures().join("gfx");
+
+    let sizes = write_assets(&out_dir, graphics_directory.to_str().unwrap(), &[level]).unwrap();
+
+    // The background's second tile is its first flipped, and the foreground's first two tiles
+    // only differ in which transparent colour they use, so 3 of their 5 tiles are kept
+    assert_eq!(sizes.0.len(), 1);
+    assert_eq!(sizes.0[0].level, "valid");
+    assert_eq!(sizes.0[0].tile_graphics, 3 * 32);
+    assert_eq!(
+        sizes.0[0].total(),
+        sizes.0[0].tile_graphics + sizes.0[0].level_data
+    );
+    assert_eq!(sizes.total(), sizes.0[0].total());
+    assert!(sizes
+        .to_string()
+        .starts_with(&format!("valid uses {} bytes of ROM", sizes.0[0].total())));
+}
+
+#[test]
+fn levels_with_the_same_images_share_their_tile_graphics() {
+    let out_dir = std::env::temp_dir().join("tiled-export-shared-assets-test");
+    // Another level with its own copy of the valid level's images
+    let graphics_directory = out_dir.join("gfx");
+    for level_file in ["valid", "copy"] {
+        std::fs::create_dir_all(graphics_directory.join(level_file)).unwrap();
+        for layer in ["background", "foreground"] {
+            std::fs::copy(
+                fixtures().join(format!("gfx/valid/valid_{layer}.png")),
+                graphics_directory.join(format!("{level_file}/{level_file}_{layer}.png")),
+            )
+            .unwrap();
+        }
+    }
+    let mut copy = load("valid").0.unwrap();
+    copy.file = "copy".to_string();
+    let levels = [load("valid").0.unwrap(), copy];
+
+    let sizes = write_assets(&out_dir, graphics_directory.to_str().unwrap(), &levels).unwrap();
+
+    let assets = std::fs::read_to_string(out_dir.join("assets.rs")).unwrap();
+    assert_eq!(assets.matches("include_background_gfx!").count(), 1);
+    assert!(assets.contains("pub use tile_set_0 as valid;"));
+    assert!(assets.contains("pub use tile_set_0 as copy;"));
+    assert_eq!(sizes.0[0].tile_set, sizes.0[1].tile_set);
+    assert_eq!(
+        sizes.total(),
+        sizes.0[0].tile_graphics + sizes.0[0].level_data + sizes.0[1].level_data
+    );
+}
+
+#[test]
+fn tmx_maps_load_the_same_as_json() {
+    let json = load("valid").0.unwrap();
+
+    for name in ["tmx_csv", "tmx_zlib"] {
+        let (level, diagnostics) = load(name);
+        let level = level.unwrap_or_else(|| panic!("{name} should load"));
+
+        assert_eq!(messages(diagnostics.errors()), Vec::<String>::new());
+        assert_eq!(level.background.tiles, json.background.tiles);
+        assert_eq!(level.foreground.tiles, json.foreground.tiles);
+        assert_eq!(
+            level.background.tile_set.flags,
+            json.background.tile_set.flags
+        );
+        assert_eq!(
+            level.background.tile_set.masks,
+            json.background.tile_set.masks
+        );
+        assert_eq!(level.player_start, json.player_start);
+        assert_eq!(level.boars, json.boars);
+        assert_eq!(level.enemy_stops, json.enemy_stops);
+        assert_eq!(level.camera_bounds, json.camera_bounds);
+        assert_eq!(level.metadata.name, json.metadata.name);
+        assert_eq!(level.metadata.par_time, json.metadata.par_time);
+        assert_eq!(
+            level.metadata.background_colour,
+            json.metadata.background_colour
+        );
+    }
+}
+
+#[test]
+fn multi_line_tmx_properties_are_read() {
+    let level = load("tmx_csv").0.unwrap();
+
+    assert_eq!(
+        level.metadata.intro.as_deref(),
+        Some("Jump over the gap\nthen reach the flag")
+    );
+}
+
+#[test]
+fn tmx_is_preferred_over_json() {
+    assert!(tiled_export::map_path(&fixtures(), "tmx_csv").ends_with("tmx_csv_map.tmx"));
+    assert!(tiled_export::map_path(&fixtures(), "valid").ends_with("valid_map.json"));
+}
+
+#[test]
+fn levels_list_every_file_they_were_read_from() {
+    let level = load("tmx_csv").0.unwrap();
+    let names = level
+        .sources
+        .iter()
+        .map(|source| source.file_name().unwrap().to_str().unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        names,
+        vec![
+            "tmx_csv_map.tmx",
+            "test_background_tile_set.tsx",
+            "test_foreground_tile_set.tsx"
+        ]
+    );
+    assert!(level.sources.iter().all(|source| source.exists()));
+    assert_eq!(
+        tiled_export::tile_set_images("gfx", &level),
+        [
+            "gfx/tmx_csv/tmx_csv_background.png",
+            "gfx/tmx_csv/tmx_csv_foreground.png"
+        ]
+    );
+}
+
+#[test]
+fn chunked_layers_have_every_tile_and_share_repeated_chunks() {
+    let map_directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("../map");
+    let level = load_level(&map_directory, "level_1", &mut Diagnostics::default()).unwrap();
+
+    for layer in [&level.background, &level.foreground] {
+        let chunked = ChunkedLayer::new(layer);
+        assert_eq!(
+            chunked.width as usize,
+            (level.width as usize).div_ceil(CHUNK_SIZE)
+        );
+        assert_eq!(
+            chunked.chunk_map.len(),
+            (chunked.width * chunked.height) as usize
+        );
+        assert!(chunked.chunks.len() < chunked.chunk_map.len());
+
+        for y in 0..level.height {
+            for x in 0..level.width {
+                assert_eq!(
+                    chunked.tile(x, y),
+                    layer.tiles[(y * level.width + x) as usize]
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn chunks_past_the_edge_of_the_level_are_padded_with_empty_tiles() {
+    let level = load("valid").0.unwrap();
+    let chunked = ChunkedLayer::new(&level.background);
+
+    assert_eq!((chunked.width, chunked.height), (1, 1));
+    assert_eq!(chunked.tile(3, 2), 1);
+    assert_eq!(chunked.tile(3, 3), 0);
+}
+
+#[test]
+fn collision_grids_share_tiles_and_keep_each_layers_mask() {
+    let level = load("flipped_tiles").0.unwrap();
+    let collision = CollisionGrid::new(&level);
+
+    assert_eq!(collision.grid.len(), 12);
+    assert!(collision.tiles.len() < collision.grid.len());
+    assert_eq!(collision.tiles[0], [(0, 0), (0, 0)]);
+
+    // Only the top half of the upside down half-solid tile collides
+    let top_row = 0xff;
+    let bottom_row = 0xff << 56;
+    assert_eq!(
+        collision.flags_in_area(0, 1, top_row),
+        tilemap::COLLISION_TILE
+    );
+    assert_eq!(collision.flags_in_area(0, 1, bottom_row), 0);
+
+    for y in 0..level.height {
+        for x in 0..level.width {
+            let flags = level.background.tile(x, y).0 | level.foreground.tile(x, y).0;
+            assert_eq!(collision.flags_in_area(x, y, u64::MAX), flags);
+        }
+    }
+}
+
+#[test]
+fn collision_grids_are_chunked_like_the_layers() {
+    let map_directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("../map");
+    let level = load_level(&map_directory, "level_1", &mut Diagnostics::default()).unwrap();
+    let collision = CollisionGrid::new(&level);
+    let chunked = collision.chunked();
+
+    assert!(chunked.chunks.len() < chunked.chunk_map.len());
+    for y in 0..level.height {
+        for x in 0..level.width {
+            assert_eq!(
+                chunked.tile(x, y),
+                collision.grid[(y * level.width + x) as usize]
+            );
+        }
+    }
+}
+
+#[test]
+fn empty_cells_have_no_flags_even_when_the_first_tile_does() {
+    let (level, diagnostics) = load("empty_cells");
+    let level = level.unwrap();
+
+    // The player starts above the floor, which would be inside a wall if empty cells were solid
+    assert_eq!(messages(diagnostics.errors()), Vec::<String>::new());
+    assert_eq!(level.background.tiles[0], 0);
+    assert_eq!(level.background.tile(0, 0), (0, 0));
+    assert_eq!(level.background.tile(0, 2).0, tilemap::COLLISION_TILE);
+
+    let collision = CollisionGrid::new(&level);
+    assert_eq!(collision.flags_in_area(0, 0, u64::MAX), 0);
+    assert_eq!(collision.flags_in_area(3, 1, u64::MAX), tilemap::WIN_TILE);
+}